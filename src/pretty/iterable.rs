@@ -1,4 +1,4 @@
-use super::aliases::Iteratable;
+use super::aliases::{Iteratable, Vector};
 
 pub trait SubsetComparable<T> {
     fn collection_equals(&self, other: &[T]) -> bool  where T: PartialEq;
@@ -13,11 +13,42 @@ impl<T>SubsetComparable<T> for [T] {
     }
 
     fn is_subset_of(&self, other: &[T]) -> bool  where T: PartialEq {
-        return self.iterate().all(|element| other.contains(&element));
+        return self.iterate().all(|element| other.contains(element));
     }
 
     fn is_superset_of(&self, other: &[T]) -> bool  where T: PartialEq {
-        return other.iterate().all(|element| self.contains(&element));
+        return other.iterate().all(|element| self.contains(element));
+    }
+}
+
+pub trait Combinable<T> {
+    fn combinations_of_size(&self, size: usize) -> Vector<Vector<T>>;
+}
+
+impl<T>Combinable<T> for [T] where T: Clone {
+    fn combinations_of_size(&self, size: usize) -> Vector<Vector<T>> {
+
+        if size == 0 {
+            return vec![vec![]];
+        }
+
+        if self.len() < size {
+            return vec![];
+        }
+
+        // Every combination either starts with the first element, or is made entirely from the rest
+        let (first, rest) = self.split_first().expect("length is at least size, which is non-zero");
+        let mut combinations: Vector<Vector<T>> = rest
+            .combinations_of_size(size - 1)
+            .into_iter()
+            .map(|mut combination| {
+                combination.insert(0, first.clone());
+                combination
+            })
+            .collect();
+
+        combinations.extend(rest.combinations_of_size(size));
+        return combinations;
     }
 }
 
@@ -28,42 +59,56 @@ mod tests {
     #[test]
     fn is_superset_of_be_true_when_this_contains_all_of_other() {
         
-        let is_superset = vec![1,2,3,4].is_superset_of(&vec![1,2,3]);
+        let is_superset = [1,2,3,4].is_superset_of(&[1,2,3]);
         assert!(is_superset);
     }
 
     #[test]
     fn is_superset_of_be_true_when_this_contains_exactly_same_elements_as_other() {
 
-        let is_superset = vec![1,2,3].is_superset_of(&vec![1,2,3]);
+        let is_superset = [1,2,3].is_superset_of(&[1,2,3]);
         assert!(is_superset);
     }
 
     #[test]
     fn is_subset_of_be_true_when_other_contains_all_of_this() {
 
-        let is_subset = vec![1,3,2].is_subset_of(&vec![2,4,3,1]);
+        let is_subset = [1,3,2].is_subset_of(&[2,4,3,1]);
         assert!(is_subset);
     }
 
     #[test]
     fn is_superset_of_be_true_when_other_contains_exactly_same_elements_as_this() {
 
-        let is_subset = vec![1,2,3,4].is_subset_of(&vec![1,3,2,4]);
+        let is_subset = [1,2,3,4].is_subset_of(&[1,3,2,4]);
         assert!(is_subset);
     }
 
     #[test]
     fn collection_equals_be_true_when_contains_same_elements() {
         
-        let is_subset = vec![1,2,5].collection_equals(&vec![2,5,1]);
+        let is_subset = [1,2,5].collection_equals(&[2,5,1]);
         assert!(is_subset);
     }
 
     #[test]
     fn collection_equals_be_false_when_any_element_different() {
         
-        let is_subset = vec![1,2,4].collection_equals(&vec![1,2,5]);
+        let is_subset = [1,2,4].collection_equals(&[1,2,5]);
         assert!(!is_subset);
     }
+
+    #[test]
+    fn combinations_of_size_gives_every_combination_in_order() {
+
+        let combinations = [1,2,3,4].combinations_of_size(2);
+        assert_eq!(combinations, vec![vec![1,2], vec![1,3], vec![1,4], vec![2,3], vec![2,4], vec![3,4]]);
+    }
+
+    #[test]
+    fn combinations_of_size_gives_nothing_when_size_too_big() {
+
+        let combinations = [1,2].combinations_of_size(3);
+        assert!(combinations.is_empty());
+    }
 }
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Sub, SubAssign};
+
+use super::consts::PUZZLE_LARGEST_DIMENTION;
+
+// Bit n is set when n is in the set, so asking whether a value is there, how many there are, or how two sets
+// compare is a single instruction rather than a search. Wide enough for the 25 values of the largest puzzles.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct CandidateSet {
+    bits: u32,
+}
+
+impl CandidateSet {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    // Every value from 1 up to the maximum
+    pub fn full(maximum_value: u8) -> Self {
+        return (1..=maximum_value).collect();
+    }
+
+    pub fn single(value: u8) -> Self {
+        return Self { bits: bit(value) };
+    }
+
+    pub fn contains(&self, value: u8) -> bool {
+        return is_storable(value) && self.bits & bit(value) != 0;
+    }
+
+    // Both give whether the set changed
+    pub fn insert(&mut self, value: u8) -> bool {
+        let was_missing = !self.contains(value);
+        self.bits |= bit(value);
+        return was_missing;
+    }
+
+    pub fn remove(&mut self, value: u8) -> bool {
+        let was_present = self.contains(value);
+        if was_present {
+            self.bits &= !bit(value);
+        }
+        return was_present;
+    }
+
+    pub fn clear(&mut self) {
+        self.bits = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.bits.count_ones() as usize;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bits == 0;
+    }
+
+    // The smallest value, which for a set of one is the only value
+    pub fn first(&self) -> Option<u8> {
+        if self.is_empty() {
+            return None;
+        }
+        return Some(self.bits.trailing_zeros() as u8);
+    }
+
+    pub fn union(self, other: CandidateSet) -> Self {
+        return Self { bits: self.bits | other.bits };
+    }
+
+    pub fn intersection(self, other: CandidateSet) -> Self {
+        return Self { bits: self.bits & other.bits };
+    }
+
+    pub fn difference(self, other: CandidateSet) -> Self {
+        return Self { bits: self.bits & !other.bits };
+    }
+
+    pub fn is_subset_of(self, other: CandidateSet) -> bool {
+        return self.bits & !other.bits == 0;
+    }
+
+    pub fn is_superset_of(self, other: CandidateSet) -> bool {
+        return other.is_subset_of(self);
+    }
+
+    // Smallest value first
+    pub fn iter(&self) -> Candidates {
+        return Candidates { bits: self.bits };
+    }
+}
+
+fn is_storable(value: u8) -> bool {
+    return (1..=PUZZLE_LARGEST_DIMENTION as u8).contains(&value);
+}
+
+fn bit(value: u8) -> u32 {
+    assert!(is_storable(value), "{value} is not a value any puzzle can hold");
+    return 1 << value;
+}
+
+pub struct Candidates {
+    bits: u32,
+}
+
+impl Iterator for Candidates {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.bits == 0 {
+            return None;
+        }
+
+        let value = self.bits.trailing_zeros() as u8;
+        // Clears the lowest set bit
+        self.bits &= self.bits - 1;
+        return Some(value);
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.bits.count_ones() as usize;
+        return (remaining, Some(remaining));
+    }
+}
+
+impl ExactSizeIterator for Candidates {}
+
+impl IntoIterator for CandidateSet {
+    type Item = u8;
+    type IntoIter = Candidates;
+
+    fn into_iter(self) -> Candidates {
+        return self.iter();
+    }
+}
+
+impl IntoIterator for &CandidateSet {
+    type Item = u8;
+    type IntoIter = Candidates;
+
+    fn into_iter(self) -> Candidates {
+        return self.iter();
+    }
+}
+
+impl FromIterator<u8> for CandidateSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(values: I) -> Self {
+        let mut set = CandidateSet::new();
+        for value in values {
+            set.insert(value);
+        }
+        return set;
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for CandidateSet {
+    fn from(values: [u8; N]) -> Self {
+        return values.into_iter().collect();
+    }
+}
+
+impl From<&[u8]> for CandidateSet {
+    fn from(values: &[u8]) -> Self {
+        return values.iter().copied().collect();
+    }
+}
+
+impl BitOr for CandidateSet {
+    type Output = CandidateSet;
+
+    fn bitor(self, other: CandidateSet) -> CandidateSet {
+        return self.union(other);
+    }
+}
+
+impl BitAnd for CandidateSet {
+    type Output = CandidateSet;
+
+    fn bitand(self, other: CandidateSet) -> CandidateSet {
+        return self.intersection(other);
+    }
+}
+
+impl Sub for CandidateSet {
+    type Output = CandidateSet;
+
+    fn sub(self, other: CandidateSet) -> CandidateSet {
+        return self.difference(other);
+    }
+}
+
+impl BitOrAssign for CandidateSet {
+    fn bitor_assign(&mut self, other: CandidateSet) {
+        *self = self.union(other);
+    }
+}
+
+impl BitAndAssign for CandidateSet {
+    fn bitand_assign(&mut self, other: CandidateSet) {
+        *self = self.intersection(other);
+    }
+}
+
+impl SubAssign for CandidateSet {
+    fn sub_assign(&mut self, other: CandidateSet) {
+        *self = self.difference(other);
+    }
+}
+
+// Shown as the values, like {1, 4, 9}, rather than the bits
+impl fmt::Debug for CandidateSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_set().entries(self.iter()).finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn membership_and_size() {
+        let mut set = CandidateSet::from([3, 1, 25]);
+
+        assert!(set.contains(1) && set.contains(3) && set.contains(25));
+        assert!(!set.contains(2) && !set.contains(0) && !set.contains(26));
+        assert_eq!(set.len(), 3);
+
+        assert!(set.insert(2));
+        assert!(!set.insert(2));
+        assert!(set.remove(25));
+        assert!(!set.remove(25));
+        assert!(!set.remove(30));
+        assert_eq!(set.len(), 3);
+        assert!(CandidateSet::new().is_empty());
+    }
+
+    #[test]
+    fn iterates_smallest_first() {
+        let set = CandidateSet::from([9, 4, 1, 16]);
+
+        assert_eq!(set.iter().collect::<Vec<u8>>(), vec![1, 4, 9, 16]);
+        assert_eq!(set.first(), Some(1));
+        assert_eq!(CandidateSet::new().first(), None);
+        assert_eq!(CandidateSet::full(9).iter().len(), 9);
+        assert_eq!(format!("{:?}", set), "{1, 4, 9, 16}");
+    }
+
+    #[test]
+    fn set_algebra() {
+        let first = CandidateSet::from([1, 2, 3]);
+        let second = CandidateSet::from([2, 3, 4]);
+
+        assert_eq!(first | second, CandidateSet::from([1, 2, 3, 4]));
+        assert_eq!(first & second, CandidateSet::from([2, 3]));
+        assert_eq!(first - second, CandidateSet::single(1));
+
+        assert!(CandidateSet::from([2, 3]).is_subset_of(first));
+        assert!(first.is_superset_of(CandidateSet::from([2, 3])));
+        assert!(!first.is_subset_of(second));
+        assert!(CandidateSet::new().is_subset_of(first));
+    }
+}
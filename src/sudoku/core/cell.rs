@@ -1,21 +1,28 @@
-use std::{collections::btree_map::Range, ops::{RangeBounds, RangeInclusive}};
+use super::{candidate_set::CandidateSet, consts::PUZZLE_MAXIMUM_VALUE};
 
-use crate::pretty::aliases::*;
-use super::consts::PUZZLE_MAXIMUM_VALUE;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Cell {
     pub value: Option<u8>,
-    pub discounted_values: Vector<u8>,
-    pub potentially_valid_values: Vector<u8>
+    // Givens are the clues the puzzle was set with, rather than values worked out while solving
+    pub is_given: bool,
+    pub discounted_values: CandidateSet,
+    pub potentially_valid_values: CandidateSet,
+    // Values run from 1 up to this, which is as many cells as are in a row of the puzzle
+    maximum_value: u8
 }
 
 impl Cell {
     pub fn new() -> Self {
+        return Cell::with_maximum_value(PUZZLE_MAXIMUM_VALUE);
+    }
+
+    pub fn with_maximum_value(maximum_value: u8) -> Self {
         Self {
             value: None,
-            discounted_values: Vector::new(),
-            potentially_valid_values: (1..=PUZZLE_MAXIMUM_VALUE).collect()
+            is_given: false,
+            discounted_values: CandidateSet::new(),
+            potentially_valid_values: CandidateSet::full(maximum_value),
+            maximum_value
         }
     }
 
@@ -29,8 +36,19 @@ impl Cell {
         return cell;
     }
 
+    pub fn from_given(value: Option<u8>) -> Self {
+        let mut cell = Cell::from_value(value);
+        cell.is_given = cell.value.is_some();
+
+        return cell;
+    }
+
+    pub fn maximum_value(&self) -> u8 {
+        return self.maximum_value;
+    }
+
     pub fn set_value(&mut self, value: u8) {
-        if !is_valid_cell_value(value){
+        if !self.is_valid_cell_value(value){
             return;
         }
         self.value = Some(value);
@@ -39,44 +57,44 @@ impl Cell {
     }
 
     pub fn discount_value(&mut self, value: u8) -> bool {
-        if !is_valid_cell_value(value){
+        if !self.is_valid_cell_value(value){
             return false;
         }
 
-        let was_removed = self.potentially_valid_values.contains(&value);
-
-        add_to_collection(&mut self.discounted_values, value);
-        remove_from_collection(&mut self.potentially_valid_values, value);
-
-        return was_removed;
+        self.discounted_values.insert(value);
+        return self.potentially_valid_values.remove(value);
     }
 
-    pub fn discount_values(&mut self, values: impl AsRef<[u8]>) -> bool {
+    pub fn discount_values(&mut self, values: impl IntoIterator<Item = u8>) -> bool {
         let mut any_removed = false;
-        for &value in values.as_ref() {
-            any_removed = any_removed | self.discount_value(value);
+        for value in values {
+            any_removed |= self.discount_value(value);
         }
 
         return any_removed;
     }
 
     pub fn discount_range(&mut self, range: impl Iterator<Item = u8>) -> bool {
-        return self.discount_values(range.collect::<Vector<u8>>());
+        return self.discount_values(range);
     }
 
     pub fn add_candidate(&mut self, value: u8) {
-        if !is_valid_cell_value(value){
+        if !self.is_valid_cell_value(value){
             return;
         }
-        add_to_collection(&mut self.potentially_valid_values, value);
-        remove_from_collection(&mut self.discounted_values, value);
+        self.potentially_valid_values.insert(value);
+        self.discounted_values.remove(value);
     }
 
     pub fn try_complete(&mut self) {
         if self.potentially_valid_values.len() == 1 {
-            self.set_value(self.potentially_valid_values[0]);
+            self.set_value(self.potentially_valid_values.first().expect("there is one candidate"));
         }
     }
+
+    fn is_valid_cell_value(&self, value: u8) -> bool{
+        return (1..=self.maximum_value).contains(&value);
+    }
 }
 
 impl Default for Cell {
@@ -85,22 +103,10 @@ impl Default for Cell {
     }
 }
 
-fn remove_from_collection<T>(collection: &mut Vector<T>, value: T) where T: PartialEq {
-    collection.retain(|x| *x != value);
-}
-
-fn add_to_collection<T>(collection : &mut Vector<T>, value: T) where T: PartialEq {
-    if!collection.contains(&value) {
-        collection.push(value);
-    }
-}
-
-fn is_valid_cell_value(value: u8) -> bool{
-    return (1..=PUZZLE_MAXIMUM_VALUE).contains(&value);
-}
-
 #[cfg(test)]
 mod tests {
+    use crate::pretty::aliases::*;
+
     use super::*;
 
     #[test]
@@ -174,4 +180,30 @@ mod tests {
         let cell = Cell::from_value(None);
         assert!(cell.value.is_none());
     }
+
+    #[test]
+    fn values_go_up_to_the_maximum_given() {
+        let mut cell = Cell::with_maximum_value(16);
+        assert_eq!(cell.potentially_valid_values, (1..=16).collect::<CandidateSet>());
+
+        cell.set_value(17);
+        assert!(cell.value.is_none());
+        cell.set_value(16);
+        assert_eq!(cell.value, Some(16));
+
+        let mut cell = Cell::with_maximum_value(4);
+        assert!(!cell.discount_value(5));
+        assert_eq!(cell.potentially_valid_values, CandidateSet::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn only_cells_from_given_values_are_givens() {
+        assert!(Cell::from_given(Some(5)).is_given);
+        assert!(!Cell::from_given(None).is_given);
+        assert!(!Cell::from_value(Some(5)).is_given);
+
+        let mut cell = Cell::new();
+        cell.set_value(5);
+        assert!(!cell.is_given);
+    }
 }
\ No newline at end of file
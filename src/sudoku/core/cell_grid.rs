@@ -1,79 +1,161 @@
 use crate::pretty::aliases::*;
-use std::{cell::RefCell, ops::{Index, IndexMut}, rc::Rc};
-use super::{cell::Cell, consts::PUZZLE_DIMENTION, puzzle::{SeedGrid, SeedRow}};
+use std::{fmt, ops::{Index, IndexMut}, slice::Chunks};
+use super::{cell::Cell, puzzle::SeedGrid, shape::Shape};
 
-pub type CellReference = Rc<RefCell<Cell>>;
-pub type RowOfReferences = [CellReference; PUZZLE_DIMENTION];
-pub type GridOfReferences = [RowOfReferences; PUZZLE_DIMENTION];
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coordinate {
+    pub row: usize,
+    pub column: usize
+}
+
+impl Coordinate {
+    pub fn new(row: usize, column: usize) -> Self {
+        Self {
+            row,
+            column
+        }
+    }
+}
 
+// Counted from one, the way a person would read the grid
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "r{}c{}", self.row + 1, self.column + 1);
+    }
+}
+
+// Every cell of the puzzle in one flat list, a row at a time. Rows, columns and blocks only hold coordinates into it,
+// so there is one owner for each cell and changing one is an ordinary mutable borrow.
+#[derive(Debug, Clone)]
 pub struct CellGrid {
-    pub grid: GridOfReferences
+    pub cells: Vector<Cell>,
+    pub shape: Shape
 }
 
 impl CellGrid{
     pub fn new() -> Self {
-        let cell_grid = empty_grid();
-        Self {
-            grid: cell_grid
-        }
+        return CellGrid::with_shape(Shape::classic());
     }
 
-    pub fn from_seed(initial_values: &SeedGrid) -> Self {
-        let cell_grid = grid_from_raw_values(&initial_values);
+    pub fn with_shape(shape: Shape) -> Self {
+        return CellGrid::from_seed(shape, &SeedGrid::new());
+    }
+
+    // The seed should be as many rows and columns across as the shape, anything outside that is left out
+    pub fn from_seed(shape: Shape, initial_values: &SeedGrid) -> Self {
+        let dimension = shape.dimension();
+        let cells = (0..dimension)
+            .flat_map(|row| (0..dimension).map(move |column| (row, column)))
+            .map(|(row, column)| given_cell(shape, initial_values.get(row).and_then(|seed_row| seed_row.get(column)).copied().flatten()))
+            .collect();
 
         Self {
-            grid: cell_grid
+            cells,
+            shape
         }
     }
-}
 
-fn grid_from_raw_values(initial_values: &SeedGrid) -> GridOfReferences {
-    return core::array::from_fn(|i| row_from_raw_values(initial_values[i]));
-}
+    pub fn coordinates(&self) -> Vector<Coordinate> {
+        let dimension = self.shape.dimension();
+        return (0..dimension).flat_map(|row| (0..dimension).map(move |column| Coordinate::new(row, column))).collect();
+    }
 
-fn row_from_raw_values(initial_values: SeedRow) -> RowOfReferences {
-    return core::array::from_fn(|i| Rc::new(RefCell::new(Cell::from_value(initial_values[i]))))
-}
+    pub fn rows(&self) -> Chunks<'_, Cell> {
+        return self.cells.chunks(self.shape.dimension());
+    }
 
-fn empty_grid() -> GridOfReferences {
-    return core::array::from_fn(|_i| empty_row_array());
+    fn index_of(&self, coordinate: Coordinate) -> usize {
+        return coordinate.row * self.shape.dimension() + coordinate.column;
+    }
 }
 
-fn empty_row_array() -> RowOfReferences {
-    return core::array::from_fn(|_i: usize| Rc::new(RefCell::new(Cell::new())));
+fn given_cell(shape: Shape, value: Option<u8>) -> Cell {
+    let mut cell = Cell::with_maximum_value(shape.maximum_value());
+
+    if let Some(value) = value {
+        cell.set_value(value);
+    }
+    cell.is_given = cell.value.is_some();
+
+    return cell;
 }
 
-// allow index syntax on the cell grid itself
+// allow index syntax on the cell grid itself, giving a row
 impl Index<usize> for CellGrid{
-    type Output = RowOfReferences;
+    type Output = [Cell];
 
     fn index(&self, index: usize) -> &Self::Output {
-        return &self.grid[index];
+        let dimension = self.shape.dimension();
+        return &self.cells[index * dimension .. (index + 1) * dimension];
     }
 }
 
 impl IndexMut<usize> for CellGrid{
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        return &mut self.grid[index];
+        let dimension = self.shape.dimension();
+        return &mut self.cells[index * dimension .. (index + 1) * dimension];
+    }
+}
+
+impl Index<Coordinate> for CellGrid{
+    type Output = Cell;
+
+    fn index(&self, coordinate: Coordinate) -> &Self::Output {
+        return &self.cells[self.index_of(coordinate)];
+    }
+}
+
+impl IndexMut<Coordinate> for CellGrid{
+    fn index_mut(&mut self, coordinate: Coordinate) -> &mut Self::Output {
+        let index = self.index_of(coordinate);
+        return &mut self.cells[index];
     }
 }
 
 #[cfg(test)]
 mod tests {
-        use super::*;
+        use crate::sudoku::core::{candidate_set::CandidateSet, consts::PUZZLE_DIMENTION};
+
+    use super::*;
 
         #[test]
         fn cell_grid_initialized_with_all_empty_cells() {
             let cell_grid = CellGrid::new();
 
             let any_cells_have_value = 
-                cell_grid.grid
+                cell_grid.cells
                 .iterate()
-                .flat_map(|row| row.iterate())
-                .any(|rc| rc.borrow().value.is_some());
+                .any(|cell| cell.value.is_some());
+
+
+            assert!(!any_cells_have_value);
+        }
 
+        #[test]
+        fn coordinates_covers_every_cell_once_in_reading_order() {
+            let cell_grid = CellGrid::new();
+            let coordinates = cell_grid.coordinates();
+
+            assert_eq!(coordinates.len(), PUZZLE_DIMENTION * PUZZLE_DIMENTION);
+            assert_eq!(coordinates[0], Coordinate::new(0, 0));
+            assert_eq!(coordinates[PUZZLE_DIMENTION + 2], Coordinate::new(1, 2));
+        }
+
+        #[test]
+        fn index_by_coordinate_gives_same_cell_as_row_and_column() {
+            let cell_grid = CellGrid::new();
+
+            assert!(std::ptr::eq(&cell_grid[Coordinate::new(3, 7)], &cell_grid[3][7]));
+        }
+
+        #[test]
+        fn grid_is_as_big_as_its_shape() {
+            let cell_grid = CellGrid::with_shape(Shape::new(2, 3).expect("test shape is valid"));
 
-            assert_eq!(any_cells_have_value, false);
+            assert_eq!(cell_grid.rows().len(), 6);
+            assert!(cell_grid.rows().all(|row| row.len() == 6));
+            assert_eq!(cell_grid.coordinates().len(), 36);
+            assert_eq!(cell_grid[5][5].potentially_valid_values, CandidateSet::full(6));
         }
 
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
+// The classic 9x9 puzzle, which is what you get unless another shape is asked for
 pub const PUZZLE_BLOCK_HEIGHT: usize = 3;
 pub const PUZZLE_BLOCK_WIDTH: usize = 3;
 pub const PUZZLE_DIMENTION: usize = PUZZLE_BLOCK_HEIGHT * PUZZLE_BLOCK_WIDTH;
 pub const PUZZLE_TOTAL_CELL_COUNT: usize = PUZZLE_DIMENTION * PUZZLE_DIMENTION;
 pub const PUZZLE_MAXIMUM_VALUE: u8 = PUZZLE_DIMENTION as u8;
+// Values are written with one symbol each, 1-9 then A-P, and that's as far as it goes
+pub const PUZZLE_LARGEST_DIMENTION: usize = 25;
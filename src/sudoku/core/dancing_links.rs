@@ -0,0 +1,345 @@
+use crate::pretty::aliases::*;
+
+use super::{cell_grid::Coordinate, puzzle::SeedGrid, shape::Shape};
+
+// Extra groups of cells that can't repeat a value, on top of the usual rows, columns and blocks
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantConstraint {
+    // Top left to bottom right
+    MainDiagonal,
+    // Top right to bottom left
+    AntiDiagonal,
+    Region(Vector<Coordinate>),
+}
+
+impl VariantConstraint {
+    pub fn cells(&self, shape: Shape) -> Vector<Coordinate> {
+        let dimension = shape.dimension();
+        return match self {
+            VariantConstraint::MainDiagonal => (0..dimension).map(|i| Coordinate::new(i, i)).collect(),
+            VariantConstraint::AntiDiagonal => (0..dimension).map(|i| Coordinate::new(i, dimension - 1 - i)).collect(),
+            VariantConstraint::Region(cells) => cells.clone(),
+        };
+    }
+}
+
+// Knuth's dancing links: a sparse matrix of doubly linked nodes, where covering a column unlinks it and every row
+// using it, and uncovering puts them back in exactly the reverse order. Nodes live in flat vectors rather than
+// behind references, index 0 is the root and the next ones are the column headers.
+struct DancingLinks {
+    left: Vector<usize>,
+    right: Vector<usize>,
+    up: Vector<usize>,
+    down: Vector<usize>,
+    column: Vector<usize>,
+    row: Vector<usize>,
+    sizes: Vector<usize>,
+}
+
+impl DancingLinks {
+    // Primary columns must be covered exactly once. Secondary columns may be covered at most once, so they never
+    // join the header list and are never chosen, but still rule out rows that clash.
+    fn new(primary_columns: usize, secondary_columns: usize) -> Self {
+
+        let header_count = primary_columns + secondary_columns + 1;
+        let mut links = Self {
+            left: Vector::with_capacity(header_count),
+            right: Vector::with_capacity(header_count),
+            up: (0..header_count).collect(),
+            down: (0..header_count).collect(),
+            column: (0..header_count).collect(),
+            row: vec![usize::MAX; header_count],
+            sizes: vec![0; header_count],
+        };
+
+        for node in 0..header_count {
+            if node <= primary_columns {
+                links.left.push(if node == 0 { primary_columns } else { node - 1 });
+                links.right.push(if node == primary_columns { 0 } else { node + 1 });
+            } else {
+                links.left.push(node);
+                links.right.push(node);
+            }
+        }
+
+        return links;
+    }
+
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+
+        let first = self.left.len();
+
+        for (index, &column) in columns.iterate().enumerate() {
+            let header = column + 1;
+            let node = first + index;
+
+            self.left.push(if index == 0 { first + columns.len() - 1 } else { node - 1 });
+            self.right.push(if index == columns.len() - 1 { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.row.push(row);
+
+            let above = self.up[header];
+            self.down[above] = node;
+            self.up[header] = node;
+            self.sizes[header] += 1;
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = right;
+        self.left[right] = left;
+
+        let mut row_node = self.down[header];
+        while row_node != header {
+            let mut node = self.right[row_node];
+            while node != row_node {
+                let (up, down) = (self.up[node], self.down[node]);
+                self.down[up] = down;
+                self.up[down] = up;
+                self.sizes[self.column[node]] -= 1;
+                node = self.right[node];
+            }
+            row_node = self.down[row_node];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut row_node = self.up[header];
+        while row_node != header {
+            let mut node = self.left[row_node];
+            while node != row_node {
+                let (up, down) = (self.up[node], self.down[node]);
+                self.sizes[self.column[node]] += 1;
+                self.down[up] = node;
+                self.up[down] = node;
+                node = self.left[node];
+            }
+            row_node = self.up[row_node];
+        }
+
+        let (left, right) = (self.left[header], self.right[header]);
+        self.right[left] = header;
+        self.left[right] = header;
+    }
+
+    // Gives true once enough solutions are found, so the search can stop
+    fn search(&mut self, chosen: &mut Vector<usize>, solutions: &mut Vector<Vector<usize>>, limit: usize) -> bool {
+
+        if self.right[0] == 0 {
+            solutions.push(chosen.clone());
+            return solutions.len() >= limit;
+        }
+
+        // The column with the fewest rows left branches the least
+        let mut header = self.right[0];
+        let mut candidate = self.right[header];
+        while candidate != 0 {
+            if self.sizes[candidate] < self.sizes[header] {
+                header = candidate;
+            }
+            candidate = self.right[candidate];
+        }
+
+        if self.sizes[header] == 0 {
+            return false;
+        }
+
+        self.cover(header);
+
+        let mut done = false;
+        let mut row_node = self.down[header];
+        while row_node != header && !done {
+
+            chosen.push(self.row[row_node]);
+            let mut node = self.right[row_node];
+            while node != row_node {
+                self.cover(self.column[node]);
+                node = self.right[node];
+            }
+
+            done = self.search(chosen, solutions, limit);
+
+            let mut node = self.left[row_node];
+            while node != row_node {
+                self.uncover(self.column[node]);
+                node = self.left[node];
+            }
+            chosen.pop();
+
+            row_node = self.down[row_node];
+        }
+
+        self.uncover(header);
+        return done;
+    }
+}
+
+pub fn find_exact_cover_solutions(shape: Shape, seed: &SeedGrid, limit: usize) -> Vector<SeedGrid> {
+    return find_exact_cover_solutions_with_variants(shape, seed, &[], limit);
+}
+
+// Works only on the seed values, so candidates eliminated on a puzzle play no part
+pub fn find_exact_cover_solutions_with_variants(shape: Shape, seed: &SeedGrid, variants: &[VariantConstraint], limit: usize) -> Vector<SeedGrid> {
+
+    if limit == 0 {
+        return vec![];
+    }
+
+    // Every cell holds one value, and every row, column and block holds each value once. Each of those is a column
+    // of the exact cover matrix, and placing a value in a cell is a matrix row covering one of each.
+    let dimension = shape.dimension();
+    let cell_columns = 0;
+    let row_columns = cell_columns + shape.cell_count();
+    let column_columns = row_columns + dimension * dimension;
+    let block_columns = column_columns + dimension * dimension;
+    let variant_columns = block_columns + dimension * dimension;
+
+    // A variant covering as many cells as there are values must hold every value, so it's primary like the standard
+    // units. A smaller region only says its values don't repeat, so it's secondary.
+    let variant_cells: Vector<Vector<Coordinate>> = variants.iterate().map(|variant| variant.cells(shape)).collect();
+    let (full, partial): (Vector<&Vector<Coordinate>>, Vector<&Vector<Coordinate>>) = variant_cells.iterate().partition(|cells| cells.len() == dimension);
+    let ordered_variants: Vector<&Vector<Coordinate>> = full.iterate().chain(partial.iterate()).copied().collect();
+
+    let primary_columns = variant_columns + full.len() * dimension;
+    let secondary_columns = partial.len() * dimension;
+    let mut links = DancingLinks::new(primary_columns, secondary_columns);
+
+    for row in 0..dimension {
+    for column in 0..dimension {
+        for value in shape.values() {
+
+            // Cells the seed doesn't reach are empty, as they are for the cell grid
+            let given = seed.get(row).and_then(|seed_row| seed_row.get(column)).copied().flatten();
+            if given.is_some_and(|given| given != value) {
+                continue;
+            }
+
+            let value_index = (value - 1) as usize;
+            let block = shape.block_of(Coordinate::new(row, column));
+
+            let mut columns = vec![
+                cell_columns + row * dimension + column,
+                row_columns + row * dimension + value_index,
+                column_columns + column * dimension + value_index,
+                block_columns + block * dimension + value_index,
+            ];
+
+            for (variant_index, cells) in ordered_variants.iterate().enumerate() {
+                if cells.contains(&Coordinate::new(row, column)) {
+                    columns.push(variant_columns + variant_index * dimension + value_index);
+                }
+            }
+
+            links.add_row(matrix_row(dimension, row, column, value), &columns);
+        }
+    }}
+
+    let mut solutions = Vector::new();
+    links.search(&mut Vector::new(), &mut solutions, limit);
+
+    return solutions.iterate().map(|rows| seed_from_rows(dimension, rows)).collect();
+}
+
+fn matrix_row(dimension: usize, row: usize, column: usize, value: u8) -> usize {
+    return (row * dimension + column) * dimension + (value - 1) as usize;
+}
+
+fn seed_from_rows(dimension: usize, rows: &[usize]) -> SeedGrid {
+
+    let mut grid = vec![vec![None; dimension]; dimension];
+
+    for &matrix_row in rows {
+        let cell = matrix_row / dimension;
+        grid[cell / dimension][cell % dimension] = Some((matrix_row % dimension) as u8 + 1);
+    }
+
+    return grid;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::{consts::PUZZLE_DIMENTION, puzzle::Puzzle, validatable_units::PuzzleValidator}, format::serializer::Serializer, techniques::backtracking_brute_force::find_solutions};
+
+    use super::*;
+
+    fn seed_from(input: StringSlice) -> SeedGrid {
+        return Serializer::new().new_puzzle(input).expect("test data is valid").to_seed();
+    }
+
+    fn all_different(grid: &SeedGrid, cells: &[Coordinate]) -> bool {
+        let mut values: Vector<u8> = cells.iterate().filter_map(|cell| grid[cell.row][cell.column]).collect();
+        let count = values.len();
+        values.sort();
+        values.dedup();
+        return values.len() == count;
+    }
+
+    #[test]
+    fn solves_puzzle_same_as_backtracking() {
+        let test_case = "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let expected = find_solutions(&mut sudoku, 1);
+
+        let solutions = find_exact_cover_solutions(Shape::classic(), &seed_from(test_case), 2);
+
+        assert_eq!(solutions, expected);
+        assert!(Puzzle::new(&solutions[0]).expect("test data is valid").is_complete());
+    }
+
+    #[test]
+    fn stops_at_limit() {
+        let empty = vec![vec![None; PUZZLE_DIMENTION]; PUZZLE_DIMENTION];
+
+        let solutions = find_exact_cover_solutions(Shape::classic(), &empty, 3);
+
+        assert_eq!(solutions.len(), 3);
+        assert_ne!(solutions[0], solutions[1]);
+        assert!(solutions.iterate().all(|solution| Puzzle::new(solution).expect("test data is valid").is_complete()));
+        assert!(find_exact_cover_solutions(Shape::classic(), &empty, 0).is_empty());
+    }
+
+    #[test]
+    fn seed_smaller_than_the_shape_leaves_the_rest_empty() {
+        let seed = vec![vec![Some(5)]];
+
+        let solutions = find_exact_cover_solutions(Shape::classic(), &seed, 1);
+
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0][0][0], Some(5));
+    }
+
+    #[test]
+    fn no_solution_when_givens_clash() {
+        let test_case = "55..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+        assert!(find_exact_cover_solutions(Shape::classic(), &seed_from(test_case), 2).is_empty());
+    }
+
+    #[test]
+    fn diagonals_are_extra_constraints() {
+        let empty = vec![vec![None; PUZZLE_DIMENTION]; PUZZLE_DIMENTION];
+        let variants = [VariantConstraint::MainDiagonal, VariantConstraint::AntiDiagonal];
+
+        let solutions = find_exact_cover_solutions_with_variants(Shape::classic(), &empty, &variants, 1);
+
+        assert_eq!(solutions.len(), 1);
+        assert!(Puzzle::new(&solutions[0]).expect("test data is valid").is_complete());
+        assert!(variants.iterate().all(|variant| all_different(&solutions[0], &variant.cells(Shape::classic()))));
+    }
+
+    #[test]
+    fn smaller_regions_only_stop_values_repeating() {
+        let mut seed = vec![vec![None; PUZZLE_DIMENTION]; PUZZLE_DIMENTION];
+        seed[0][0] = Some(1);
+        let region = vec![Coordinate::new(0, 0), Coordinate::new(4, 4), Coordinate::new(8, 8)];
+
+        let solutions = find_exact_cover_solutions_with_variants(Shape::classic(), &seed, &[VariantConstraint::Region(region.clone())], 5);
+
+        assert_eq!(solutions.len(), 5);
+        assert!(solutions.iterate().all(|solution| all_different(solution, &region)));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::pretty::aliases::*;
+
+use super::{candidate_set::CandidateSet, cell::Cell, cell_grid::{CellGrid, Coordinate}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    SetValue(u8),
+    DiscountValue(u8),
+    AddCandidate(u8),
+}
+
+impl Operation {
+    fn apply_to(self, cell: &mut Cell) {
+        match self {
+            Operation::SetValue(value) => cell.set_value(value),
+            Operation::DiscountValue(value) => { cell.discount_value(value); },
+            Operation::AddCandidate(value) => cell.add_candidate(value),
+        }
+    }
+}
+
+// What an operation changed in a cell and nothing else, so undoing it leaves alone whatever the techniques have done
+// to the cell since. Setting a value throws away the candidates, so those are kept to give back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Delta {
+    value_before: Option<u8>,
+    value_after: Option<u8>,
+    candidates_removed: CandidateSet,
+    candidates_added: CandidateSet,
+    discounted_removed: CandidateSet,
+    discounted_added: CandidateSet,
+}
+
+impl Delta {
+    fn between(before: &Cell, after: &Cell) -> Self {
+        return Self {
+            value_before: before.value,
+            value_after: after.value,
+            candidates_removed: before.potentially_valid_values - after.potentially_valid_values,
+            candidates_added: after.potentially_valid_values - before.potentially_valid_values,
+            discounted_removed: before.discounted_values - after.discounted_values,
+            discounted_added: after.discounted_values - before.discounted_values,
+        };
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.value_before == self.value_after
+            && self.candidates_removed.is_empty()
+            && self.candidates_added.is_empty()
+            && self.discounted_removed.is_empty()
+            && self.discounted_added.is_empty();
+    }
+
+    fn reverse(&self, cell: &mut Cell) {
+        if self.value_before != self.value_after {
+            cell.value = self.value_before;
+        }
+        cell.potentially_valid_values = (cell.potentially_valid_values | self.candidates_removed) - self.candidates_added;
+        cell.discounted_values = (cell.discounted_values | self.discounted_removed) - self.discounted_added;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub coordinate: Coordinate,
+    pub operation: Operation,
+    delta: Delta,
+}
+
+// Changes made straight to the grid, as the techniques make them, aren't moves. A checkpoint keeps the whole grid
+// so going back to one undoes those as well. It keeps the moves too, as they may have been undone and replaced since.
+#[derive(Debug, Clone)]
+struct Checkpoint {
+    name: String,
+    cells: Vector<Cell>,
+    done: Vector<Move>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    done: Vector<Move>,
+    undone: Vector<Move>,
+    checkpoints: Vector<Checkpoint>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    // Only an operation that changed the cell is kept, so there is never a move that undoes to nothing.
+    // A new move means the undone ones can't be redone any more.
+    pub fn make(&mut self, cell_grid: &mut CellGrid, coordinate: Coordinate, operation: Operation) -> bool {
+        let delta = apply(cell_grid, coordinate, operation);
+        if delta.is_empty() {
+            return false;
+        }
+
+        self.done.push(Move { coordinate, operation, delta });
+        self.undone.clear();
+        return true;
+    }
+
+    pub fn undo(&mut self, cell_grid: &mut CellGrid) -> bool {
+        let Some(last) = self.done.pop() else {
+            return false;
+        };
+
+        last.delta.reverse(&mut cell_grid[last.coordinate]);
+        self.undone.push(last);
+        return true;
+    }
+
+    pub fn redo(&mut self, cell_grid: &mut CellGrid) -> bool {
+        let Some(mut next) = self.undone.pop() else {
+            return false;
+        };
+
+        // The cell may have changed since the move was undone, so what the operation changes this time is what counts
+        next.delta = apply(cell_grid, next.coordinate, next.operation);
+        if next.delta.is_empty() {
+            return false;
+        }
+
+        self.done.push(next);
+        return true;
+    }
+
+    pub fn checkpoint(&mut self, name: StringSlice, cell_grid: &CellGrid) {
+        self.checkpoints.push(Checkpoint {
+            name: name.to_string(),
+            cells: cell_grid.cells.clone(),
+            done: self.done.clone(),
+        });
+    }
+
+    // Goes back to the latest checkpoint with the name. The moves and checkpoints since are forgotten rather than
+    // left to redo, as they may have been made on top of changes the techniques made.
+    pub fn rewind_to(&mut self, name: StringSlice, cell_grid: &mut CellGrid) -> bool {
+        let Some(index) = self.checkpoints.iterate().rposition(|checkpoint| checkpoint.name == name) else {
+            return false;
+        };
+
+        let checkpoint = &self.checkpoints[index];
+        cell_grid.cells.clone_from_slice(&checkpoint.cells);
+        self.done.clone_from(&checkpoint.done);
+        self.undone.clear();
+        self.checkpoints.truncate(index + 1);
+        return true;
+    }
+
+    // Oldest first
+    pub fn moves(&self) -> &[Move] {
+        return &self.done;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        return !self.done.is_empty();
+    }
+
+    pub fn can_redo(&self) -> bool {
+        return !self.undone.is_empty();
+    }
+}
+
+fn apply(cell_grid: &mut CellGrid, coordinate: Coordinate, operation: Operation) -> Delta {
+    let cell = &mut cell_grid[coordinate];
+    let before = cell.clone();
+    operation.apply_to(cell);
+
+    return Delta::between(&before, cell);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::puzzle::Puzzle, format::serializer::Serializer, techniques::{implicitly_solved::SingleCandidateCells, solver::{StepResult, Technique}}};
+
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_step_through_moves() {
+        let mut sudoku = Puzzle::default();
+        let cell = Coordinate::new(0, 0);
+
+        sudoku.discount_value(cell, 4);
+        sudoku.set_value(cell, 7);
+
+        assert!(sudoku.undo());
+        assert_eq!(sudoku.cell_grid[cell].value, None);
+        assert!(!sudoku.cell_grid[cell].potentially_valid_values.contains(4));
+        assert_eq!(sudoku.cell_grid[cell].potentially_valid_values.len(), 8);
+
+        assert!(sudoku.undo());
+        assert!(sudoku.cell_grid[cell].potentially_valid_values.contains(4));
+        assert!(!sudoku.undo());
+
+        assert!(sudoku.redo());
+        assert!(sudoku.redo());
+        assert_eq!(sudoku.cell_grid[cell].value, Some(7));
+        assert!(!sudoku.redo());
+    }
+
+    #[test]
+    fn new_move_clears_redo_and_unchanged_cells_are_not_moves() {
+        let mut sudoku = Puzzle::default();
+        let cell = Coordinate::new(3, 5);
+
+        sudoku.discount_value(cell, 2);
+        assert!(!sudoku.discount_value(cell, 2));
+        sudoku.add_candidate(cell, 9);
+        assert_eq!(sudoku.history().moves().len(), 1);
+
+        sudoku.undo();
+        assert!(sudoku.history().can_redo());
+        sudoku.add_candidate(cell, 2);
+        sudoku.set_value(cell, 1);
+
+        assert!(!sudoku.history().can_redo());
+        assert_eq!(sudoku.history().moves().iterate().map(|change| change.operation).collect::<Vector<_>>(), vec![Operation::SetValue(1)]);
+    }
+
+    #[test]
+    fn redo_that_no_longer_changes_the_cell_is_not_a_move() {
+        let mut sudoku = Puzzle::default();
+        let cell = Coordinate::new(2, 6);
+
+        sudoku.discount_value(cell, 3);
+        sudoku.undo();
+        sudoku.cell_grid[cell].discount_values(vec![3]);
+
+        assert!(!sudoku.redo());
+        assert!(!sudoku.history().can_undo());
+        assert!(!sudoku.history().can_redo());
+    }
+
+    #[test]
+    fn undo_keeps_what_a_technique_did_to_the_cell_since() {
+        let mut sudoku = Puzzle::default();
+        let (cell, peer) = (Coordinate::new(0, 0), Coordinate::new(0, 8));
+
+        sudoku.discount_value(cell, 4);
+        sudoku.set_value(peer, 9);
+        assert!(matches!(SingleCandidateCells.apply(&mut sudoku), StepResult::Progressed(_)));
+        assert!(!sudoku.cell_grid[cell].potentially_valid_values.contains(9));
+
+        assert!(sudoku.undo());
+        assert_eq!(sudoku.cell_grid[peer].value, None);
+        assert_eq!(sudoku.cell_grid[peer].potentially_valid_values.len(), 9);
+
+        assert!(sudoku.undo());
+        assert!(sudoku.cell_grid[cell].potentially_valid_values.contains(4));
+        assert!(!sudoku.cell_grid[cell].potentially_valid_values.contains(9));
+    }
+
+    #[test]
+    fn rewinding_to_a_checkpoint_undoes_what_a_technique_did() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        sudoku.set_value(Coordinate::new(0, 1), 1);
+        let before = sudoku.cell_grid.cells.clone();
+
+        sudoku.checkpoint(SingleCandidateCells.name());
+        assert!(matches!(SingleCandidateCells.apply(&mut sudoku), StepResult::Progressed(_)));
+        sudoku.set_value(Coordinate::new(8, 8), 3);
+
+        assert!(sudoku.rewind_to(SingleCandidateCells.name()));
+        assert_eq!(sudoku.cell_grid.cells, before);
+        assert_eq!(sudoku.history().moves().len(), 1);
+        assert!(!sudoku.rewind_to("Never made"));
+    }
+
+    #[test]
+    fn rewinding_after_undo_brings_back_the_moves_at_the_checkpoint() {
+        let mut sudoku = Puzzle::default();
+        let (first, second) = (Coordinate::new(0, 0), Coordinate::new(1, 1));
+
+        sudoku.set_value(first, 5);
+        sudoku.checkpoint("Start");
+        let at_checkpoint = sudoku.cell_grid.cells.clone();
+
+        sudoku.undo();
+        sudoku.set_value(second, 6);
+
+        assert!(sudoku.rewind_to("Start"));
+        assert_eq!(sudoku.cell_grid.cells, at_checkpoint);
+        assert_eq!(sudoku.history().moves().iterate().map(|change| (change.coordinate, change.operation)).collect::<Vector<_>>(), vec![(first, Operation::SetValue(5))]);
+
+        assert!(sudoku.undo());
+        assert_eq!(sudoku.cell_grid[first].value, None);
+        assert!(!sudoku.undo());
+    }
+}
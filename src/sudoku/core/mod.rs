@@ -1,5 +1,9 @@
 pub mod puzzle;
+pub mod candidate_set;
 pub mod cell;
 pub mod cell_grid;
 pub mod consts;
+pub mod dancing_links;
+pub mod history;
+pub mod shape;
 pub mod validatable_units;
\ No newline at end of file
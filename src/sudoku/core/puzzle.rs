@@ -1,9 +1,17 @@
 use crate::pretty::aliases::*;
 
-use super::{cell_grid::{CellGrid, GridOfReferences}, consts::{PUZZLE_BLOCK_HEIGHT, PUZZLE_BLOCK_WIDTH, PUZZLE_DIMENTION}, validatable_units::{CellGroup, CellGroupValidator, PuzzleValidator, UnitValidator}};
+use super::{cell_grid::{CellGrid, Coordinate}, dancing_links::find_exact_cover_solutions, history::{History, Operation}, shape::Shape, validatable_units::{CellGroup, CellGroupValidator, PuzzleValidator, UnitValidator}};
 
-pub type SeedRow = [Option<u8>; PUZZLE_DIMENTION];
-pub type SeedGrid = [SeedRow; PUZZLE_DIMENTION];
+pub type SeedRow = Vector<Option<u8>>;
+pub type SeedGrid = Vector<SeedRow>;
+
+#[derive(Debug, PartialEq)]
+pub enum SolutionCount {
+    NoSolution,
+    Unique(SeedGrid),
+    // Holds how many were found before giving up, so is a lower bound rather than the full count
+    Multiple(usize),
+}
 
 pub struct Puzzle {
     pub cell_grid: CellGrid,
@@ -11,92 +19,383 @@ pub struct Puzzle {
     pub columns: Vector<CellGroup>,
     pub blocks: Vector<CellGroup>,
     unit_validator: UnitValidator,
+    history: History,
 }
 
 impl Puzzle {
     pub fn default() -> Self {
-        let cell_grid =  CellGrid::new();
-        let rows = (0..PUZZLE_DIMENTION).map(|i| get_row(i, &cell_grid.grid)).collect();
-        let columns = (0..PUZZLE_DIMENTION).map(|i| get_column(i, &cell_grid.grid)).collect();
-        let mut blocks = Vector::<CellGroup>::new();
-
-        for x in 0 .. PUZZLE_BLOCK_HEIGHT {
-        for y in 0 .. PUZZLE_BLOCK_WIDTH {
-            blocks.push(get_block(x, y, &cell_grid.grid));
-        }}
+        return Puzzle::with_shape(Shape::classic());
+    }
 
-        Self {
-            cell_grid,
-            rows,
-            columns,
-            blocks,
-            unit_validator: UnitValidator::new()
+    pub fn with_shape(shape: Shape) -> Self {
+        return Puzzle::from_cell_grid(CellGrid::with_shape(shape));
+    }
+
+    // The blocks are the usual shape for the size of the seed, so a 6x6 seed gets blocks 2 tall and 3 wide.
+    // Sizes with no block shape, like 7x7, can't be a puzzle.
+    pub fn new(seed: &SeedGrid) -> Result<Self, String> {
+        let dimension = seed.len();
+
+        if seed.iterate().any(|row| row.len() != dimension) {
+            return Err(format!("A seed needs as many columns as rows, and this one has {dimension} rows"));
         }
+
+        let Some(shape) = Shape::for_dimension(dimension) else {
+            return Err(format!("A {dimension}x{dimension} puzzle has no block shape to give it"));
+        };
+
+        return Ok(Puzzle::from_seed(shape, seed));
     }
 
-    pub fn new(seed: &[[Option<u8>; PUZZLE_DIMENTION]; PUZZLE_DIMENTION]) -> Self {
-        //todo: Split out what's copied here, even though constructor bits are awkward with referenced items
-        let cell_grid =  CellGrid::from_seed(seed);
-        let rows = (0..PUZZLE_DIMENTION).map(|i| get_row(i, &cell_grid.grid)).collect();
-        let columns = (0..PUZZLE_DIMENTION).map(|i| get_column(i, &cell_grid.grid)).collect();
-        let mut blocks = Vector::<CellGroup>::new();
+    pub fn from_seed(shape: Shape, seed: &SeedGrid) -> Self {
+        return Puzzle::from_cell_grid(CellGrid::from_seed(shape, seed));
+    }
 
-        for x in 0 .. PUZZLE_BLOCK_HEIGHT {
-        for y in 0 .. PUZZLE_BLOCK_WIDTH {
-            blocks.push(get_block(x, y, &cell_grid.grid));
-        }}
+    fn from_cell_grid(cell_grid: CellGrid) -> Self {
+        let shape = cell_grid.shape;
+        let rows = (0..shape.dimension()).map(|i| get_row(shape, i)).collect();
+        let columns = (0..shape.dimension()).map(|i| get_column(shape, i)).collect();
+        let blocks = (0..shape.dimension()).map(|i| get_block(shape, i)).collect();
 
         Self {
             cell_grid,
             rows,
             columns,
             blocks,
-            unit_validator: UnitValidator::new()
+            unit_validator: UnitValidator::new(),
+            history: History::new()
         }
     }
 
+    pub fn shape(&self) -> Shape {
+        return self.cell_grid.shape;
+    }
+
+    // Changing a cell through these rather than through the grid makes the change a move that can be undone
+    pub fn set_value(&mut self, cell: Coordinate, value: u8) {
+        self.history.make(&mut self.cell_grid, cell, Operation::SetValue(value));
+    }
+
+    pub fn discount_value(&mut self, cell: Coordinate, value: u8) -> bool {
+        return self.history.make(&mut self.cell_grid, cell, Operation::DiscountValue(value));
+    }
+
+    pub fn add_candidate(&mut self, cell: Coordinate, value: u8) {
+        self.history.make(&mut self.cell_grid, cell, Operation::AddCandidate(value));
+    }
+
+    pub fn undo(&mut self) -> bool {
+        return self.history.undo(&mut self.cell_grid);
+    }
+
+    pub fn redo(&mut self) -> bool {
+        return self.history.redo(&mut self.cell_grid);
+    }
+
+    // Taking one before running a technique lets rewind_to go back to how the puzzle was before it
+    pub fn checkpoint(&mut self, name: StringSlice) {
+        self.history.checkpoint(name, &self.cell_grid);
+    }
+
+    pub fn rewind_to(&mut self, name: StringSlice) -> bool {
+        return self.history.rewind_to(name, &mut self.cell_grid);
+    }
+
+    pub fn history(&self) -> &History {
+        return &self.history;
+    }
+
     pub fn count_cells_with_value(&self) -> usize {
-        return self.cell_grid.grid.iterate().flatten().filter(|&rc| rc.borrow().value.is_some()).count();
+        return self.cell_grid.cells.iterate().filter(|cell| cell.value.is_some()).count();
+    }
+
+    pub fn units_containing(&self, cell: Coordinate) -> Vector<&CellGroup> {
+        return self.rows.iterate()
+            .chain(self.columns.iterate())
+            .chain(self.blocks.iterate())
+            .filter(|group| group.contains(cell))
+            .collect();
+    }
+
+    // Two different cells see each other when they share a row, column or block, so can't hold the same value
+    pub fn sees(&self, cell: Coordinate, other: Coordinate) -> bool {
+        return cell != other && self.units_containing(cell).iterate().any(|group| group.contains(other));
+    }
+
+    pub fn peers_of(&self, cell: Coordinate) -> Vector<Coordinate> {
+        let mut peers: Vector<Coordinate> = Vector::new();
+
+        for group in self.units_containing(cell) {
+            for &group_cell in group.cells.iterate() {
+                if group_cell != cell && !peers.contains(&group_cell) {
+                    peers.push(group_cell);
+                }
+            }
+        }
+
+        return peers;
+    }
+
+    pub fn to_seed(&self) -> SeedGrid {
+        return self.cell_grid.rows().map(|row| row.iterate().map(|cell| cell.value).collect()).collect();
+    }
+
+    // Searching needs at least two solutions to tell a unique puzzle apart, so smaller limits are raised to 2.
+    // Only the values placed so far are searched from, and the puzzle itself is left alone.
+    pub fn count_solutions(&self, limit: usize) -> SolutionCount {
+        let mut solutions = find_exact_cover_solutions(self.shape(), &self.to_seed(), limit.max(2));
+
+        return match solutions.len() {
+            0 => SolutionCount::NoSolution,
+            1 => SolutionCount::Unique(solutions.remove(0)),
+            found => SolutionCount::Multiple(found),
+        };
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        return matches!(self.count_solutions(2), SolutionCount::Unique(_));
+    }
+}
+
+// Rows, columns and blocks only hold coordinates, so a clone gets groups that already point into its own grid.
+// The history is left behind: clones are made to try things out, and copying every move and checkpoint each time
+// would cost more than the trial itself.
+impl Clone for Puzzle {
+    fn clone(&self) -> Self {
+        return Self {
+            cell_grid: self.cell_grid.clone(),
+            rows: self.rows.clone(),
+            columns: self.columns.clone(),
+            blocks: self.blocks.clone(),
+            unit_validator: self.unit_validator.clone(),
+            history: History::new(),
+        };
     }
 }
 
-fn get_block(block_row_number: usize, block_column_number:usize, cell_grid: &GridOfReferences) -> CellGroup {
+// Asking the puzzle whether two cells see each other means searching its units, which is too slow inside the bigger searches
+pub struct PeerTable {
+    peers: Vector<bool>,
+    shape: Shape
+}
 
-    let row_range_lower_index = block_row_number * PUZZLE_BLOCK_HEIGHT;
-    let row_range_upper_index = block_row_number * PUZZLE_BLOCK_HEIGHT + PUZZLE_BLOCK_HEIGHT;
-    let column_range_lower_index = block_column_number * PUZZLE_BLOCK_WIDTH;
-    let column_range_upper_index = block_column_number * PUZZLE_BLOCK_WIDTH + PUZZLE_BLOCK_WIDTH;
+impl PeerTable {
+    pub fn new(sudoku: &Puzzle) -> Self {
 
-    let cells  = cell_grid[row_range_lower_index .. row_range_upper_index]
-    .iterate()
-    .flat_map(|row| row[column_range_lower_index .. column_range_upper_index].iterate())
-    .cloned()
-    .collect();
+        let shape = sudoku.shape();
+        let mut peers = vec![false; shape.cell_count() * shape.cell_count()];
 
-    return CellGroup::new(cells);
+        for group in sudoku.rows.iterate().chain(sudoku.columns.iterate()).chain(sudoku.blocks.iterate()) {
+            for &first in &group.cells {
+            for &second in &group.cells {
+                if first != second {
+                    peers[peer_index(shape, first, second)] = true;
+                }
+            }}
+        }
+
+        return Self {
+            peers,
+            shape
+        };
+    }
+
+    pub fn sees(&self, first: Coordinate, second: Coordinate) -> bool {
+        return self.peers[peer_index(self.shape, first, second)];
+    }
+}
+
+fn peer_index(shape: Shape, first: Coordinate, second: Coordinate) -> usize {
+    let first_index = first.row * shape.dimension() + first.column;
+    let second_index = second.row * shape.dimension() + second.column;
+    return first_index * shape.cell_count() + second_index;
 }
 
-fn get_row(row_number: usize, cell_grid: &GridOfReferences) -> CellGroup {
-    let cells = cell_grid[row_number].iterate().map(|cell| cell.clone()).collect();
+fn get_block(shape: Shape, block: usize) -> CellGroup {
+
+    let origin = shape.block_origin(block);
+
+    let cells = (origin.row .. origin.row + shape.block_height)
+        .flat_map(|row| (origin.column .. origin.column + shape.block_width).map(move |column| Coordinate::new(row, column)))
+        .collect();
+
     return CellGroup::new(cells);
 }
 
-fn get_column(column_number: usize, cell_grid: &GridOfReferences) -> CellGroup {
+fn get_row(shape: Shape, row_number: usize) -> CellGroup {
+    let cells = (0..shape.dimension()).map(|column| Coordinate::new(row_number, column)).collect();
+    return CellGroup::new(cells);
+}
 
-    let cells = cell_grid.iterate().map(|row| row[column_number].clone()).collect();
+fn get_column(shape: Shape, column_number: usize) -> CellGroup {
+    let cells = (0..shape.dimension()).map(|row| Coordinate::new(row, column_number)).collect();
     return CellGroup::new(cells);
 }
 
 impl PuzzleValidator for Puzzle {
     fn is_valid(&self) -> bool {
-            return self.rows.iterate().all(|r| self.unit_validator.is_valid(r))
-            && self.columns.iterate().all(|r| self.unit_validator.is_valid(r))
-            && self.blocks.iterate().all(|r| self.unit_validator.is_valid(r));
+            return self.rows.iterate().all(|r| self.unit_validator.is_valid(&self.cell_grid, r))
+            && self.columns.iterate().all(|r| self.unit_validator.is_valid(&self.cell_grid, r))
+            && self.blocks.iterate().all(|r| self.unit_validator.is_valid(&self.cell_grid, r));
     }
 
     fn is_complete(&self) -> bool {
-        return self.rows.iterate().all(|r| self.unit_validator.is_complete(r))
-        && self.columns.iterate().all(|r| self.unit_validator.is_complete(r))
-        && self.blocks.iterate().all(|r| self.unit_validator.is_complete(r));
+        return self.rows.iterate().all(|r| self.unit_validator.is_complete(&self.cell_grid, r))
+        && self.columns.iterate().all(|r| self.unit_validator.is_complete(&self.cell_grid, r))
+        && self.blocks.iterate().all(|r| self.unit_validator.is_complete(&self.cell_grid, r));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::format::serializer::Serializer;
+
+    use super::*;
+
+    #[test]
+    fn sees_true_for_cells_sharing_a_unit() {
+        let sudoku = Puzzle::default();
+        let cell = Coordinate::new(4, 4);
+
+        assert!(sudoku.sees(cell, Coordinate::new(4, 0)));
+        assert!(sudoku.sees(cell, Coordinate::new(8, 4)));
+        assert!(sudoku.sees(cell, Coordinate::new(3, 5)));
+        assert!(!sudoku.sees(cell, Coordinate::new(3, 6)));
+        assert!(!sudoku.sees(cell, cell));
+    }
+
+    #[test]
+    fn peers_of_gives_each_other_cell_in_row_column_and_block_once() {
+        let sudoku = Puzzle::default();
+        let cell = Coordinate::new(0, 0);
+
+        let peers = sudoku.peers_of(cell);
+
+        assert_eq!(peers.len(), 20);
+        assert!(peers.iterate().all(|&peer| sudoku.sees(cell, peer)));
+    }
+
+    #[test]
+    fn non_square_blocks_are_two_rows_of_three() {
+        let sudoku = Puzzle::with_shape(Shape::new(2, 3).expect("test shape is valid"));
+
+        assert_eq!((sudoku.rows.len(), sudoku.columns.len(), sudoku.blocks.len()), (6, 6, 6));
+        assert!(sudoku.blocks.iterate().all(|block| block.cells.len() == 6));
+        assert!(sudoku.blocks[1].contains(Coordinate::new(1, 3)));
+        assert!(sudoku.blocks[2].contains(Coordinate::new(2, 0)));
+        assert!(sudoku.sees(Coordinate::new(0, 0), Coordinate::new(1, 2)));
+        assert!(!sudoku.sees(Coordinate::new(0, 0), Coordinate::new(2, 1)));
+        assert_eq!(sudoku.peers_of(Coordinate::new(0, 0)).len(), 12);
+    }
+
+    #[test]
+    fn peer_table_agrees_with_sees() {
+        let sudoku = Puzzle::default();
+        let peers = PeerTable::new(&sudoku);
+
+        for first in sudoku.cell_grid.coordinates() {
+        for second in sudoku.cell_grid.coordinates() {
+            assert_eq!(peers.sees(first, second), sudoku.sees(first, second));
+        }}
+    }
+
+    #[test]
+    fn changes_to_a_clone_stay_in_the_clone() {
+        let mut sudoku = Puzzle::default();
+        sudoku.cell_grid[0][0].set_value(5);
+
+        let mut copy = sudoku.clone();
+        copy.cell_grid[0][1].set_value(7);
+        copy.cell_grid[1][1].discount_value(3);
+        sudoku.cell_grid[8][8].set_value(1);
+
+        assert_eq!(copy.cell_grid[0][0].value, Some(5));
+        assert_eq!(sudoku.cell_grid[0][1].value, None);
+        assert!(sudoku.cell_grid[1][1].potentially_valid_values.contains(3));
+        assert_eq!(copy.cell_grid[8][8].value, None);
+        assert_eq!(copy.rows[0].cells_in(&copy.cell_grid).filter(|cell| cell.value.is_some()).count(), 2);
+        assert_eq!(sudoku.rows[0].cells_in(&sudoku.cell_grid).filter(|cell| cell.value.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn clone_starts_with_no_history() {
+        let mut sudoku = Puzzle::default();
+        sudoku.set_value(Coordinate::new(0, 0), 5);
+        sudoku.checkpoint("Start");
+
+        let mut copy = sudoku.clone();
+
+        assert_eq!(copy.cell_grid[0][0].value, Some(5));
+        assert!(!copy.history().can_undo());
+        assert!(!copy.rewind_to("Start"));
+        assert!(sudoku.history().can_undo());
+    }
+
+    #[test]
+    fn new_returns_err_for_sizes_with_no_block_shape() {
+        for dimension in [7, 11, 26] {
+            assert!(Puzzle::new(&vec![vec![None; dimension]; dimension]).is_err());
+        }
+
+        assert!(Puzzle::new(&vec![vec![None; 9]; 6]).is_err());
+        assert_eq!(Puzzle::new(&vec![vec![None; 6]; 6]).expect("test data is valid").shape(), Shape::new(2, 3).expect("test shape is valid"));
+    }
+
+    #[test]
+    fn count_solutions_gives_solution_when_unique() {
+        let serializer = Serializer::new();
+        let sudoku = serializer.new_puzzle("53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79").expect("test data is valid");
+        let expected = serializer.new_puzzle("534678912672195348198342567859761423426853791713924856961537284287419635345286179").expect("test data is valid").to_seed();
+
+        assert_eq!(sudoku.count_solutions(2), SolutionCount::Unique(expected));
+        assert!(sudoku.has_unique_solution());
+    }
+
+    #[test]
+    fn count_solutions_gives_multiple_up_to_limit() {
+        let sudoku = Puzzle::default();
+
+        assert_eq!(sudoku.count_solutions(2), SolutionCount::Multiple(2));
+        assert_eq!(sudoku.count_solutions(5), SolutionCount::Multiple(5));
+        assert!(!sudoku.has_unique_solution());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn count_solutions_works_for_other_sizes() {
+        let serializer = Serializer::new();
+        let sudoku = serializer.new_puzzle(".24...........13").expect("test data is valid");
+        let expected = serializer.new_puzzle("3241413213242413").expect("test data is valid").to_seed();
+
+        assert_eq!(sudoku.count_solutions(2), SolutionCount::Unique(expected));
+        assert_eq!(Puzzle::with_shape(Shape::new(2, 3).expect("test shape is valid")).count_solutions(2), SolutionCount::Multiple(2));
+    }
+
+    #[test]
+    fn count_solutions_finds_second_solution_of_deadly_pattern() {
+        // The 6s and 7s where rows 1 and 4 cross columns 4 and 5 can be swapped
+        let sudoku = Serializer::new().new_puzzle("534..8912672195348198342567859..1423426853791713924856961537284287419635345286179").expect("test data is valid");
+
+        assert_eq!(sudoku.count_solutions(10), SolutionCount::Multiple(2));
+    }
+
+    #[test]
+    fn count_solutions_gives_no_solution_when_givens_clash() {
+        let sudoku = Serializer::new().new_puzzle("11...............................................................................").expect("test data is valid");
+
+        assert_eq!(sudoku.count_solutions(2), SolutionCount::NoSolution);
+    }
+
+    #[test]
+    fn count_solutions_leaves_puzzle_unchanged() {
+        let test_case = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let serializer = Serializer::new();
+        let sudoku = serializer.new_puzzle(test_case).expect("test data is valid");
+        let candidates_before: Vector<_> = sudoku.cell_grid.cells.iterate().map(|cell| cell.potentially_valid_values).collect();
+
+        sudoku.count_solutions(2);
+
+        let candidates_after: Vector<_> = sudoku.cell_grid.cells.iterate().map(|cell| cell.potentially_valid_values).collect();
+        assert_eq!(serializer.serialize(&sudoku), test_case);
+        assert_eq!(candidates_before, candidates_after);
+    }
+}
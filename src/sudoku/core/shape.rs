@@ -0,0 +1,115 @@
+use std::ops::RangeInclusive;
+
+use super::{cell_grid::Coordinate, consts::{PUZZLE_BLOCK_HEIGHT, PUZZLE_BLOCK_WIDTH, PUZZLE_LARGEST_DIMENTION}};
+
+// Everything about a puzzle's size follows from its blocks. A block holds each value once, so there are as many values
+// as cells in a block, and the grid is that many cells across. Blocks don't have to be square: a 6x6 puzzle has blocks
+// 2 rows tall and 3 columns wide, stacked 3 high and 2 across.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shape {
+    pub block_height: usize,
+    pub block_width: usize,
+}
+
+impl Shape {
+    pub fn new(block_height: usize, block_width: usize) -> Result<Self, String> {
+        if !(1..=PUZZLE_LARGEST_DIMENTION).contains(&(block_height * block_width)) {
+            return Err(format!("A {block_height}x{block_width} block can't be used, as it needs to hold from 1 to {PUZZLE_LARGEST_DIMENTION} values"));
+        }
+
+        return Ok(Self {
+            block_height,
+            block_width
+        });
+    }
+
+    pub fn classic() -> Self {
+        return Self {
+            block_height: PUZZLE_BLOCK_HEIGHT,
+            block_width: PUZZLE_BLOCK_WIDTH
+        };
+    }
+
+    // The usual block shape for a grid this many cells across: as square as possible, and wider than it is tall when it can't be.
+    // Gives nothing for sizes with no block shape, like a prime number of cells across.
+    pub fn for_dimension(dimension: usize) -> Option<Self> {
+        if !(1..=PUZZLE_LARGEST_DIMENTION).contains(&dimension) {
+            return None;
+        }
+
+        return (1..=dimension)
+            .rev()
+            .find(|&height| height * height <= dimension && dimension.is_multiple_of(height))
+            .filter(|&height| height > 1)
+            .and_then(|height| Shape::new(height, dimension / height).ok());
+    }
+
+    pub fn dimension(&self) -> usize {
+        return self.block_height * self.block_width;
+    }
+
+    pub fn cell_count(&self) -> usize {
+        return self.dimension() * self.dimension();
+    }
+
+    pub fn maximum_value(&self) -> u8 {
+        return self.dimension() as u8;
+    }
+
+    pub fn values(&self) -> RangeInclusive<u8> {
+        return 1..=self.maximum_value();
+    }
+
+    // Blocks are numbered across and then down, the same way as the cells in a row
+    pub fn block_of(&self, coordinate: Coordinate) -> usize {
+        let blocks_across = self.dimension() / self.block_width;
+        return (coordinate.row / self.block_height) * blocks_across + coordinate.column / self.block_width;
+    }
+
+    // The top left cell of the block
+    pub fn block_origin(&self, block: usize) -> Coordinate {
+        let blocks_across = self.dimension() / self.block_width;
+        return Coordinate::new((block / blocks_across) * self.block_height, (block % blocks_across) * self.block_width);
+    }
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        return Shape::classic();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_dimension_picks_the_squarest_blocks() {
+        assert_eq!(Shape::for_dimension(4), Shape::new(2, 2).ok());
+        assert_eq!(Shape::for_dimension(6), Shape::new(2, 3).ok());
+        assert_eq!(Shape::for_dimension(9), Some(Shape::classic()));
+        assert_eq!(Shape::for_dimension(12), Shape::new(3, 4).ok());
+        assert_eq!(Shape::for_dimension(16), Shape::new(4, 4).ok());
+        assert_eq!(Shape::for_dimension(25), Shape::new(5, 5).ok());
+        assert_eq!(Shape::for_dimension(7), None);
+        assert_eq!(Shape::for_dimension(36), None);
+    }
+
+    #[test]
+    fn blocks_of_non_square_shape_are_numbered_across_then_down() {
+        let shape = Shape::new(2, 3).expect("test shape is valid");
+
+        assert_eq!(shape.block_of(Coordinate::new(0, 2)), 0);
+        assert_eq!(shape.block_of(Coordinate::new(1, 3)), 1);
+        assert_eq!(shape.block_of(Coordinate::new(2, 0)), 2);
+        assert_eq!(shape.block_of(Coordinate::new(5, 5)), 5);
+        assert_eq!(shape.block_origin(3), Coordinate::new(2, 3));
+    }
+
+    #[test]
+    fn new_returns_err_when_block_holds_too_many_or_no_values() {
+        assert!(Shape::new(5, 6).is_err());
+        assert!(Shape::new(0, 3).is_err());
+        assert_eq!(Shape::new(2, 3), Ok(Shape { block_height: 2, block_width: 3 }));
+    }
+}
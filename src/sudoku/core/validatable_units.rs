@@ -1,6 +1,5 @@
 use crate::pretty::aliases::*;
-use std::{cell::RefCell, rc::Rc};
-use super::{cell::Cell, cell_grid::CellReference};
+use super::{cell::Cell, cell_grid::{CellGrid, Coordinate}};
 
 pub trait PuzzleValidator {
     fn is_valid(&self) -> bool;
@@ -8,10 +7,11 @@ pub trait PuzzleValidator {
 }
 
 pub trait CellGroupValidator {
-    fn is_valid(&self, cells: &CellGroup) -> bool;
-    fn is_complete(&self, cells: &CellGroup) -> bool;
+    fn is_valid(&self, cell_grid: &CellGrid, cells: &CellGroup) -> bool;
+    fn is_complete(&self, cell_grid: &CellGrid, cells: &CellGroup) -> bool;
 }
 
+#[derive(Clone)]
 pub struct UnitValidator {
 }
 
@@ -22,130 +22,158 @@ impl UnitValidator {
 }
 
 impl CellGroupValidator for UnitValidator {
-    fn is_valid(&self, cell_group: &CellGroup) -> bool {
+    fn is_valid(&self, cell_grid: &CellGrid, cell_group: &CellGroup) -> bool {
 
         let all_cell_values: Vector<u8> = 
             cell_group
-            .cells
-            .iterate()
-            .filter_map(|rc| rc.borrow().value)
+            .cells_in(cell_grid)
+            .filter_map(|cell| cell.value)
             .collect();
 
         let mut deduped: Vector<u8> = all_cell_values.to_vec();
+        deduped.sort();
         deduped.dedup();
 
         return all_cell_values.len() == deduped.len();
 
     }
 
-    fn is_complete(&self, cell_group: &CellGroup) -> bool {
-        return self.is_valid(cell_group) &&
+    fn is_complete(&self, cell_grid: &CellGrid, cell_group: &CellGroup) -> bool {
+        return self.is_valid(cell_grid, cell_group) &&
             cell_group
-            .cells
-            .iterate()
-            .all(|rc| rc.borrow().value.is_some());
+            .cells_in(cell_grid)
+            .all(|cell| cell.value.is_some());
 
     }
 }
+
+// A row, column or block, as the coordinates of its cells in the grid. The grid owns the cells, so the group is
+// only a lookup table, and is the same for every puzzle of the same shape.
+#[derive(Debug, Clone, PartialEq)]
 pub struct CellGroup {
-    pub cells: Vector<CellReference>
+    pub cells: Vector<Coordinate>
 }
 
 impl CellGroup {
-    pub fn new(cells: Vector<CellReference>) -> Self {
+    pub fn new(cells: Vector<Coordinate>) -> Self {
         Self {
             cells
         }
     }
+
+    pub fn contains(&self, coordinate: Coordinate) -> bool {
+        return self.cells.contains(&coordinate);
+    }
+
+    pub fn cells_in<'a>(&'a self, cell_grid: &'a CellGrid) -> impl Iterator<Item = &'a Cell> {
+        return self.cells.iterate().map(|&coordinate| &cell_grid[coordinate]);
+    }
+
+    // The group's cells that have no value yet
+    pub fn unsolved(&self, cell_grid: &CellGrid) -> Vector<Coordinate> {
+        return self.cells.iterate().copied().filter(|&coordinate| cell_grid[coordinate].value.is_none()).collect();
+    }
+
+    // For a row this is the column numbers where the value could go, and for a column the row numbers
+    pub fn positions_with_candidate(&self, cell_grid: &CellGrid, value: u8) -> Vector<usize> {
+        return self.cells_in(cell_grid)
+            .enumerate()
+            .filter(|(_, cell)| cell.potentially_valid_values.contains(value))
+            .map(|(position, _)| position)
+            .collect();
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::rc::Rc;
     use super::*;
 
-    fn cell_reference_from_value(cell_value_option: Option<u8>) -> CellReference {
-        
-        let new_cell_ref = Rc::new(RefCell::new(Cell::new()));
-        if let Some(value) = cell_value_option {
-            new_cell_ref.borrow_mut().set_value(value);
+    // The first row of an empty grid, with the values given put along it
+    fn group_from_values(values: &[Option<u8>]) -> (CellGrid, CellGroup) {
+
+        let mut cell_grid = CellGrid::new();
+        let cells: Vector<Coordinate> = (0..values.len()).map(|column| Coordinate::new(0, column)).collect();
+
+        for (&coordinate, value) in cells.iterate().zip(values) {
+            if let Some(value) = value {
+                cell_grid[coordinate].set_value(*value);
+            }
         }
-        return new_cell_ref;
+        return (cell_grid, CellGroup::new(cells));
     }
 
     #[test]
     fn is_valid_true_when_empty_cells() {
 
-        let cells = vec![
-            cell_reference_from_value(None),
-            cell_reference_from_value(None),
-        ];
-
-        let group = CellGroup::new(cells);
+        let (cell_grid, group) = group_from_values(&[None, None]);
         let unit_validator = UnitValidator::new();
 
-        assert_eq!(unit_validator.is_valid(&group), true);
+        assert!(unit_validator.is_valid(&cell_grid, &group));
     }
 
     #[test]
     fn is_valid_true_when_different_values() {
-        
-        let cells = vec![
-            cell_reference_from_value(None),
-            cell_reference_from_value(None),
-            cell_reference_from_value(Some(1)),
-            cell_reference_from_value(Some(2))
-        ];
-
-        let group = CellGroup::new(cells);
+
+        let (cell_grid, group) = group_from_values(&[None, None, Some(1), Some(2)]);
         let unit_validator = UnitValidator::new();
 
-        assert_eq!(unit_validator.is_valid(&group), true);
+        assert!(unit_validator.is_valid(&cell_grid, &group));
     }
 
     #[test]
     fn is_complete_false_when_different_values_but_some_none() {
 
-        let cells = vec![
-            cell_reference_from_value(None),
-            cell_reference_from_value(Some(1)),
-            cell_reference_from_value(Some(2))
-        ];
-
-        let group = CellGroup::new(cells);
+        let (cell_grid, group) = group_from_values(&[None, Some(1), Some(2)]);
         let unit_validator = UnitValidator::new();
 
-        assert_eq!(unit_validator.is_complete(&group), false);
+        assert!(!unit_validator.is_complete(&cell_grid, &group));
     }
 
     #[test]
     fn is_complete_true_when_different_values_but_and_all_have_values() {
 
-        let cells = vec![
-            cell_reference_from_value(Some(1)),
-            cell_reference_from_value(Some(2)),
-            cell_reference_from_value(Some(3)),
-            cell_reference_from_value(Some(4)),
-            cell_reference_from_value(Some(5)),
-        ];
-
-        let group = CellGroup::new(cells);
+        let (cell_grid, group) = group_from_values(&[Some(1), Some(2), Some(3), Some(4), Some(5)]);
         let unit_validator = UnitValidator::new();
 
-        assert_eq!(unit_validator.is_complete(&group), true);
+        assert!(unit_validator.is_complete(&cell_grid, &group));
     }
 
     #[test]
     fn is_valid_false_when_duplicates() {
-        
-        let cells = vec![
-            cell_reference_from_value(Some(1)),
-            cell_reference_from_value(Some(1))
-        ];
 
-        let group = CellGroup::new(cells);
+        let (cell_grid, group) = group_from_values(&[Some(1), Some(1)]);
         let unit_validator = UnitValidator::new();
 
-        assert_eq!(unit_validator.is_valid(&group), false);
+        assert!(!unit_validator.is_valid(&cell_grid, &group));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn is_valid_false_when_duplicates_are_not_next_to_each_other() {
+
+        let (cell_grid, group) = group_from_values(&[Some(1), Some(2), Some(1)]);
+        let unit_validator = UnitValidator::new();
+
+        assert!(!unit_validator.is_valid(&cell_grid, &group));
+    }
+
+    #[test]
+    fn contains_true_only_for_the_groups_cells() {
+
+        let (_, group) = group_from_values(&[Some(1)]);
+
+        assert!(group.contains(Coordinate::new(0, 0)));
+        assert!(!group.contains(Coordinate::new(0, 1)));
+        assert!(!group.contains(Coordinate::new(1, 0)));
+    }
+
+    #[test]
+    fn positions_with_candidate_gives_index_of_each_cell_with_candidate() {
+
+        let (mut cell_grid, group) = group_from_values(&[None, Some(3), None, None]);
+        cell_grid[0][2].discount_value(4);
+
+        assert_eq!(group.positions_with_candidate(&cell_grid, 4), vec![0, 3]);
+        assert_eq!(group.positions_with_candidate(&cell_grid, 3), vec![0, 2, 3]);
+        assert_eq!(group.unsolved(&cell_grid), vec![Coordinate::new(0, 0), Coordinate::new(0, 2), Coordinate::new(0, 3)]);
+    }
+}
@@ -1,18 +1,13 @@
-use crate::{pretty::aliases::*, sudoku::core::cell_grid::RowOfReferences};
+use crate::sudoku::core::{cell::Cell, cell_grid::CellGrid, puzzle::Puzzle};
+use crate::sudoku::format::serializer::symbol_for;
 
-use crate::sudoku::core::{cell_grid::CellGrid, consts::{PUZZLE_BLOCK_HEIGHT, PUZZLE_BLOCK_WIDTH, PUZZLE_DIMENTION}, validatable_units::CellGroup};
 
 
+pub fn draw_all_rows(sudoku: &Puzzle) {
+    print!("\n OK Drawing the rows now\n");
 
-pub fn draw_all_rows(rows: &Vector<CellGroup>) {
-    print!("\n OK Drawing the rows now, the Weak<Refcell<Cell>>\n");
-
-    for row in rows {
-        let drawable_row_result = row.cells.clone().try_into();
-        
-        if let Ok(_) = drawable_row_result { 
-            draw_row(&drawable_row_result.expect("object is in Ok arm of match"));
-        }
+    for row in sudoku.cell_grid.rows() {
+        draw_row(row, sudoku.shape().block_width);
     }
 }
 
@@ -20,32 +15,33 @@ pub fn draw_all_cells(cell_grid: &CellGrid){
 
     println!("Ok let's try drawing the whole grid!");
 
-    let separator_line_length = create_row_line(&cell_grid[0]).len();
+    let shape = cell_grid.shape;
+    let separator_line_length = create_row_line(&cell_grid[0], shape.block_width).len();
 
     draw_separator_line(separator_line_length);
-    for i in 0..PUZZLE_DIMENTION {
-        draw_row(&cell_grid[i]);
-        draw_separator_line(separator_line_length);     
-        if include_extra_separator(i.try_into().unwrap(), PUZZLE_BLOCK_HEIGHT as u8) {
-            draw_separator_line(separator_line_length);     
+    for i in 0..shape.dimension() {
+        draw_row(&cell_grid[i], shape.block_width);
+        draw_separator_line(separator_line_length);
+        if include_extra_separator(i, shape.block_height, shape.dimension()) {
+            draw_separator_line(separator_line_length);
         }
     }
 }
 
-fn draw_row(row: &RowOfReferences) {
-    println!("{}", create_row_line(row));
+fn draw_row(row: &[Cell], block_width: usize) {
+    println!("{}", create_row_line(row, block_width));
 }
 
-fn create_row_line(row: &RowOfReferences) -> String {
+fn create_row_line(row: &[Cell], block_width: usize) -> String {
 
     let mut row_line_display: String = "|".to_owned();
 
-    for i in 0..PUZZLE_DIMENTION {
+    for i in 0..row.len() {
         row_line_display.push(' ');
-        row_line_display.push_str(value_or_letter_x(&row[i].borrow().value).as_str());
+        row_line_display.push_str(value_or_letter_x(&row[i].value).as_str());
         row_line_display.push_str(" |");
-     
-        if include_extra_separator(i.try_into().unwrap(), PUZZLE_BLOCK_WIDTH as u8) {
+
+        if include_extra_separator(i, block_width, row.len()) {
             row_line_display.push('|');
         }
     }
@@ -53,17 +49,17 @@ fn create_row_line(row: &RowOfReferences) -> String {
     return row_line_display;
 }
 
-fn include_extra_separator(index: u8, if_divisible_by: u8) -> bool{
-    return (1 + index) % if_divisible_by == 0 && (1 + index) != PUZZLE_DIMENTION as u8;
+fn include_extra_separator(index: usize, if_divisible_by: usize, dimension: usize) -> bool{
+    return (1 + index).is_multiple_of(if_divisible_by) && (1 + index) != dimension;
 }
 
 fn value_or_letter_x(value: &Option<u8>) -> String {
     return match value {
         None => "x".into(),
-        _ => value.as_ref().unwrap().to_string()
+        _ => symbol_for(value.unwrap()).to_string()
     };
 }
 
 fn draw_separator_line(length: usize) {
     println!("{}", "_".repeat(length));
-}
\ No newline at end of file
+}
@@ -1,13 +1,16 @@
 // It's hard to find formatting information, so we'll create one for now and hope for the best.
 // The standard format is in the form [1-9.]{81}
-// I'm imagining that we eventually support other sizes, in which case we will cover that when we get there.
+// Other sizes are written the same way, a row at a time, with values past 9 as letters from A. So a 4x4 puzzle is
+// [1-4.]{16} and a 16x16 one [1-9A-G.]{256}. The size follows from the length, and the blocks are the usual shape for it.
 
-use crate::{pretty::aliases::*, sudoku::core::consts::PUZZLE_TOTAL_CELL_COUNT};
+use crate::{pretty::aliases::*, sudoku::core::consts::PUZZLE_LARGEST_DIMENTION};
 use regex::Regex;
 
-use crate::sudoku::core::{consts::PUZZLE_DIMENTION, puzzle::{Puzzle, SeedGrid}};
+use crate::sudoku::core::{cell_grid::Coordinate, puzzle::{Puzzle, SeedGrid}, shape::Shape};
+use crate::sudoku::techniques::step::{SolveLog, Step, Unit};
 
-const NINE_X_NINE_CELL_REGEX: StringSlice = "^[1-9.]{81}$";
+const CELL_REGEX: StringSlice = "^[1-9A-P.]+$";
+const VALUE_SYMBOLS: StringSlice = "123456789ABCDEFGHIJKLMNOP";
 
 pub struct Serializer {
     regex: Regex
@@ -16,70 +19,191 @@ pub struct Serializer {
 impl Serializer {
     pub fn new() -> Self {
         Self {
-            regex: Regex::new(NINE_X_NINE_CELL_REGEX).expect("expecting a valid regex here")
+            regex: Regex::new(CELL_REGEX).expect("expecting a valid regex here")
         }
     }
 
     pub fn can_parse(&self, input: StringSlice) -> bool {
-        return self.regex.is_match(input);
+        return dimension_for_length(input).is_some_and(|dimension| Shape::for_dimension(dimension).is_some() && self.is_notation_for(dimension, input));
     }
 
+    pub fn can_parse_with_shape(&self, shape: Shape, input: StringSlice) -> bool {
+        return self.is_notation_for(shape.dimension(), input);
+    }
+
+    // Whether a size has a block shape is left to Puzzle::new, so the error says why a size can't be used
     pub fn new_puzzle(&self, input: StringSlice) -> Result<Puzzle, String> {
-        
-        if !self.can_parse(input) {
-            return Err(format!("The input '{input}' wasn't understood as notation for a sudoku puzzle. Expected a string matching {NINE_X_NINE_CELL_REGEX}"));
+
+        let dimension = dimension_for_length(input).filter(|&dimension| self.is_notation_for(dimension, input));
+        let Some(dimension) = dimension else {
+            return Err(format!("The input '{input}' wasn't understood as notation for a sudoku puzzle. Expected a string of {} characters for a {}x{} puzzle, or of another square size, each one a value or '.'", Shape::classic().cell_count(), Shape::classic().dimension(), Shape::classic().dimension()));
+        };
+
+        return Puzzle::new(&values_from_input(dimension, input));
+    }
+
+    // For when the blocks aren't the usual shape for the size, like a 6x6 puzzle with blocks 3 tall and 2 wide
+    pub fn new_puzzle_with_shape(&self, shape: Shape, input: StringSlice) -> Result<Puzzle, String> {
+
+        if !self.can_parse_with_shape(shape, input) {
+            let symbols = &VALUE_SYMBOLS[..shape.dimension()];
+            return Err(format!("The input '{input}' wasn't understood as notation for a {}x{} sudoku puzzle. Expected {} characters, each one of '{symbols}' or '.'", shape.dimension(), shape.dimension(), shape.cell_count()));
         }
 
-        let values = values_from_input(input);
-        return Ok(Puzzle::new(&values));
+        let values = values_from_input(shape.dimension(), input);
+        return Ok(Puzzle::from_seed(shape, &values));
+    }
+
+    fn is_notation_for(&self, dimension: usize, input: StringSlice) -> bool {
+        return self.regex.is_match(input)
+            && input.chars().count() == dimension * dimension
+            && input.chars().filter_map(value_for).all(|value| (1..=dimension).contains(&(value as usize)));
     }
 
     pub fn serialize(&self, sudoku: &Puzzle) -> String {
-        let mut serialized = String::with_capacity(PUZZLE_TOTAL_CELL_COUNT);
-        for row in 0..PUZZLE_DIMENTION {
-        for column in 0..PUZZLE_DIMENTION {
-    
-            let push = match sudoku.cell_grid[row][column].borrow().value {
-                Some(cell_value) => cell_value.to_string(),
-                None => ".".to_string(),
+        let mut serialized = String::with_capacity(sudoku.shape().cell_count());
+        for row in 0..sudoku.shape().dimension() {
+        for column in 0..sudoku.shape().dimension() {
+
+            let push = match sudoku.cell_grid[row][column].value {
+                Some(cell_value) => symbol_for(cell_value),
+                None => '.',
             };
-            
-            serialized.push_str(push.as_str());
+
+            serialized.push(push);
         }}
-    
+
         return serialized;
     }
+
+    // One step per line, as technique|cells|units|removed|placed, with cells like r3c5 and candidates like r3c5:7
+    pub fn serialize_log(&self, log: &SolveLog) -> String {
+        return log.steps
+            .iterate()
+            .map(|step| {
+                let fields = [
+                    step.technique.clone(),
+                    join(&step.cells, |cell| cell.to_string()),
+                    join(&step.units, |unit| unit.to_string()),
+                    join(&step.removed, |(cell, value)| format!("{cell}:{value}")),
+                    join(&step.placed, |(cell, value)| format!("{cell}:{value}")),
+                ];
+                fields.join("|") + "\n"
+            })
+            .collect();
+    }
+
+    pub fn new_log(&self, input: StringSlice) -> Result<SolveLog, String> {
+        return self.new_log_with_shape(Shape::classic(), input);
+    }
+
+    // Cells, units and values are checked against the size of puzzle the log is for
+    pub fn new_log_with_shape(&self, shape: Shape, input: StringSlice) -> Result<SolveLog, String> {
+
+        let mut log = SolveLog::new();
+
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let fields: Vector<StringSlice> = line.split('|').collect();
+            let [technique, cells, units, removed, placed] = fields[..] else {
+                return Err(format!("The step '{line}' wasn't understood. Expected five fields, as technique|cells|units|removed|placed"));
+            };
+
+            let mut step = Step::new(technique);
+            step.cells = split(cells).map(|cell| coordinate_from(shape, cell)).collect::<Result<_, _>>()?;
+            step.units = split(units).map(|unit| unit_from(shape, unit)).collect::<Result<_, _>>()?;
+            step.removed = split(removed).map(|candidate| candidate_from(shape, candidate)).collect::<Result<_, _>>()?;
+            step.placed = split(placed).map(|candidate| candidate_from(shape, candidate)).collect::<Result<_, _>>()?;
+            log.push(step);
+        }
+
+        return Ok(log);
+    }
+}
+
+// Counted from 1, as the value 1 is written '1'
+pub fn symbol_for(value: u8) -> char {
+    return VALUE_SYMBOLS.chars().nth(value as usize - 1).unwrap_or('?');
+}
+
+pub fn value_for(symbol: char) -> Option<u8> {
+    return VALUE_SYMBOLS.find(symbol).map(|index| index as u8 + 1);
 }
 
-fn values_from_input(input: StringSlice) -> SeedGrid {
-    let mut grid = [[None; PUZZLE_DIMENTION]; PUZZLE_DIMENTION];
-    
+fn dimension_for_length(input: StringSlice) -> Option<usize> {
+    let length = input.chars().count();
+    return (1..=PUZZLE_LARGEST_DIMENTION).find(|dimension| dimension * dimension == length);
+}
+
+fn values_from_input(dimension: usize, input: StringSlice) -> SeedGrid {
+    let mut grid = vec![vec![None; dimension]; dimension];
+
     for (index, c) in input.chars().enumerate() {
-        let row = index / PUZZLE_DIMENTION;
-        let column = index % PUZZLE_DIMENTION;
-        
+        let row = index / dimension;
+        let column = index % dimension;
+
         match c {
             '.' => grid[row][column] = None,
             _ => {
-                if let Some(value) = c.to_digit(10).map(|digit| digit as u8) {
+                if let Some(value) = value_for(c) {
                     grid[row][column] = Some(value);
                 }
             }
         }
     }
-    
+
     return grid;
-} 
+}
+
+fn join<T>(items: &[T], describe: impl Fn(&T) -> String) -> String {
+    return items.iterate().map(describe).collect::<Vector<String>>().join(", ");
+}
+
+fn split(field: StringSlice) -> impl Iterator<Item = StringSlice> {
+    return field.split(", ").filter(|item| !item.is_empty());
+}
+
+fn index_from(shape: Shape, input: StringSlice) -> Option<usize> {
+    return input.parse::<usize>().ok().filter(|&number| (1..=shape.dimension()).contains(&number)).map(|number| number - 1);
+}
+
+fn coordinate_from(shape: Shape, input: StringSlice) -> Result<Coordinate, String> {
+    return input
+        .strip_prefix('r')
+        .and_then(|rest| rest.split_once('c'))
+        .and_then(|(row, column)| Some(Coordinate::new(index_from(shape, row)?, index_from(shape, column)?)))
+        .ok_or(format!("The cell '{input}' wasn't understood. Expected one like r3c5"));
+}
+
+fn unit_from(shape: Shape, input: StringSlice) -> Result<Unit, String> {
+    let unit = match input.split_once(' ') {
+        Some(("row", index)) => index_from(shape, index).map(Unit::Row),
+        Some(("column", index)) => index_from(shape, index).map(Unit::Column),
+        Some(("block", index)) => index_from(shape, index).map(Unit::Block),
+        _ => None,
+    };
+
+    return unit.ok_or(format!("The unit '{input}' wasn't understood. Expected one like row 3, column 5 or block 9"));
+}
+
+fn candidate_from(shape: Shape, input: StringSlice) -> Result<(Coordinate, u8), String> {
+    let Some((cell, value)) = input.split_once(':') else {
+        return Err(format!("The candidate '{input}' wasn't understood. Expected one like r3c5:7"));
+    };
+
+    let value = value.parse::<u8>().ok().filter(|value| shape.values().contains(value));
+    return match value {
+        Some(value) => Ok((coordinate_from(shape, cell)?, value)),
+        None => Err(format!("The candidate '{input}' wasn't understood. Expected a value from 1 to {}", shape.maximum_value())),
+    };
+}
 
 #[cfg(test)]
 mod tests {
-    use std::{clone};
-
-    use crate::sudoku::{core::consts::{self, PUZZLE_MAXIMUM_VALUE, PUZZLE_TOTAL_CELL_COUNT}, format::serializer};
+    use crate::sudoku::core::consts::{self, PUZZLE_DIMENTION, PUZZLE_MAXIMUM_VALUE, PUZZLE_TOTAL_CELL_COUNT};
     use super::*;
 
     fn repeat_value_times(repeat_this: StringSlice, times: usize) -> String {
-        let repeated_values:Vector<String> = std::iter::repeat(repeat_this.to_string()).take(times).collect();
+        let repeated_values:Vector<String> = std::iter::repeat_n(repeat_this.to_string(), times).collect();
         return repeated_values.join("");
     }
 
@@ -94,9 +218,9 @@ mod tests {
     fn can_parse_return_false_when_not_exactly_81_elements() {
         let serializer = Serializer::new();
         let can_parse_80 = serializer.can_parse(&repeat_value_times(".", PUZZLE_TOTAL_CELL_COUNT - 1));
-        assert_eq!(can_parse_80, false);
+        assert!(!can_parse_80);
         let can_parse_82 = serializer.can_parse(&repeat_value_times(".", PUZZLE_TOTAL_CELL_COUNT + 1));
-        assert_eq!(can_parse_82, false);
+        assert!(!can_parse_82);
     }
 
     #[test]
@@ -139,7 +263,7 @@ mod tests {
     fn can_parse_return_false_when_any_invalid_character() {
         let serializer = Serializer::new();
         let can_parse = serializer.can_parse(&repeat_value_times("a", PUZZLE_TOTAL_CELL_COUNT));
-        assert_eq!(can_parse, false);
+        assert!(!can_parse);
     }
 
     #[test]
@@ -155,7 +279,7 @@ mod tests {
         let empty = repeat_value_times(".", PUZZLE_TOTAL_CELL_COUNT);
         let result = serializer.new_puzzle(&empty);
         assert!(result.is_ok());
-        result.unwrap().cell_grid.grid.iterate().flat_map(|x| x.iterate()).for_each(|cell_ref| assert_eq!(cell_ref.borrow().value, None))
+        result.unwrap().cell_grid.cells.iterate().for_each(|cell| assert_eq!(cell.value, None))
     }
 
     #[test]
@@ -169,7 +293,7 @@ mod tests {
         let result = serializer.new_puzzle(&string_representation);
         
         assert!(result.is_ok());
-        let found_value = result.unwrap().cell_grid.grid[0][0].borrow().value;
+        let found_value = result.unwrap().cell_grid[0][0].value;
         assert!(found_value.is_some());
         assert_eq!(found_value.unwrap(), expected)
     }
@@ -180,8 +304,8 @@ mod tests {
 
         let string_representation = repeat_value_times("123456789", PUZZLE_DIMENTION);
         
-        let row: [Option<u8>; PUZZLE_DIMENTION] = (1..=PUZZLE_MAXIMUM_VALUE).map(|value| Some(value)).collect::<Vector<_>>().try_into().unwrap();
-        let expected_values: [[Option<u8>; PUZZLE_DIMENTION]; PUZZLE_DIMENTION] = core::array::from_fn(|_i| row.clone());
+        let row: Vector<Option<u8>> = (1..=PUZZLE_MAXIMUM_VALUE).map(Some).collect();
+        let expected_values: SeedGrid = vec![row; PUZZLE_DIMENTION];
 
         let result = serializer.new_puzzle(&string_representation);
         assert!(result.is_ok());
@@ -190,7 +314,7 @@ mod tests {
 
         for row in 0..PUZZLE_DIMENTION {
         for column in 0..PUZZLE_DIMENTION {
-            let actual = sudoku.cell_grid[row][column].borrow().value;
+            let actual = sudoku.cell_grid[row][column].value;
             assert!(actual.is_some());
             let expected = expected_values[row][column].expect("there should be a value here according to the test setup");
             
@@ -198,4 +322,95 @@ mod tests {
         }}
     }
 
+    #[test]
+    fn deserialize_then_serialize_other_sizes_returns_same_string() {
+        let serializer = Serializer::new();
+
+        let test_cases = vec![
+            ".24...........13".to_string(),
+            "5......24......6...31..5......1...56".to_string(),
+            repeat_value_times("123456789ABCDEFG", 16),
+            repeat_value_times(".", 625),
+        ];
+
+        for expected in test_cases {
+            let sudoku = serializer.new_puzzle(&expected).expect("test data is confirmed correct");
+            assert_eq!(serializer.serialize(&sudoku), expected);
+        }
+    }
+
+    #[test]
+    fn size_follows_from_length() {
+        let serializer = Serializer::new();
+
+        let sudoku = serializer.new_puzzle("5......24......6...31..5......1...56").expect("test data is valid");
+        assert_eq!(sudoku.shape(), Shape::new(2, 3).expect("test shape is valid"));
+
+        let sudoku = serializer.new_puzzle(&repeat_value_times("G", 256)).expect("test data is valid");
+        assert_eq!(sudoku.shape(), Shape::new(4, 4).expect("test shape is valid"));
+        assert_eq!(sudoku.cell_grid[15][15].value, Some(16));
+    }
+
+    #[test]
+    fn new_puzzle_returns_err_when_value_too_big_for_size() {
+        let serializer = Serializer::new();
+
+        assert!(serializer.new_puzzle(&repeat_value_times("A", PUZZLE_TOTAL_CELL_COUNT)).is_err());
+        assert!(serializer.new_puzzle("5......24......6...31..5......1...57").is_err());
+        assert!(serializer.new_puzzle(&repeat_value_times(".", 49)).is_err());
+    }
+
+    #[test]
+    fn new_puzzle_returns_err_from_puzzle_when_size_has_no_block_shape() {
+        let serializer = Serializer::new();
+
+        for dimension in [7, 11] {
+            let input = repeat_value_times(".", dimension * dimension);
+            assert!(!serializer.can_parse(&input));
+            assert_eq!(serializer.new_puzzle(&input).err(), Puzzle::new(&vec![vec![None; dimension]; dimension]).err());
+        }
+
+        assert!(serializer.new_puzzle(&repeat_value_times(".", 26 * 26)).is_err());
+    }
+
+    #[test]
+    fn new_puzzle_with_shape_uses_blocks_given() {
+        let serializer = Serializer::new();
+        let test_case = "....3.56.......1...4.....1...62.4...";
+
+        assert!(!serializer.can_parse_with_shape(Shape::classic(), test_case));
+
+        let sudoku = serializer.new_puzzle_with_shape(Shape::new(3, 2).expect("test shape is valid"), test_case).expect("test data is valid");
+        assert_eq!(sudoku.shape(), Shape::new(3, 2).expect("test shape is valid"));
+        assert!(sudoku.blocks[0].contains(Coordinate::new(2, 1)));
+        assert_eq!(serializer.serialize(&sudoku), test_case);
+    }
+
+    #[test]
+    fn log_round_trips_through_text() {
+        let serializer = Serializer::new();
+
+        let mut step = Step::new("Hidden subsets")
+            .with_units([Unit::Row(0), Unit::Block(8)])
+            .with_cells([Coordinate::new(0, 0), Coordinate::new(8, 8)]);
+        step.removed = vec![(Coordinate::new(0, 0), 3), (Coordinate::new(8, 8), 9)];
+        let placement = Step { placed: vec![(Coordinate::new(4, 4), 5)], ..Step::new("Single candidate cells") };
+        let log = SolveLog { steps: vec![step, placement] };
+
+        let serialized = serializer.serialize_log(&log);
+        assert_eq!(serialized.lines().next(), Some("Hidden subsets|r1c1, r9c9|row 1, block 9|r1c1:3, r9c9:9|"));
+        assert_eq!(serializer.new_log(&serialized), Ok(log));
+    }
+
+    #[test]
+    fn new_log_returns_err_when_invalid_input() {
+        let serializer = Serializer::new();
+
+        assert!(serializer.new_log("Hidden singles|r1c1").is_err());
+        assert!(serializer.new_log("Hidden singles|r0c1|||").is_err());
+        assert!(serializer.new_log("Hidden singles||aisle 3||").is_err());
+        assert!(serializer.new_log("Hidden singles||||r1c1:10").is_err());
+        assert!(serializer.new_log_with_shape(Shape::new(4, 4).expect("test shape is valid"), "Hidden singles||||r16c1:10").is_ok());
+        assert!(serializer.new_log_with_shape(Shape::new(2, 2).expect("test shape is valid"), "Hidden singles||||r1c5:1").is_err());
+    }
 }
\ No newline at end of file
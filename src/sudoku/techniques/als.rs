@@ -0,0 +1,528 @@
+use crate::pretty::aliases::*;
+use std::collections::HashSet;
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::Coordinate, puzzle::{PeerTable, Puzzle}};
+
+use super::{solver::{StepResult, Technique}, step::Step};
+
+// Stems with more candidates than this need a petal for each, and the search for those grows far faster than it pays off
+const LARGEST_DEATH_BLOSSOM_STEM: usize = 3;
+
+// As many cells as a set can have in a classic puzzle, so nothing is lost there. On bigger grids the larger sets are
+// rare, and looking for them would mean trying nearly every group of cells in a unit.
+const LARGEST_ALMOST_LOCKED_SET: usize = 8;
+
+// N cells in one unit between them holding N + 1 candidates. Lose any one of those candidates and the rest are locked in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlmostLockedSet {
+    pub cells: Vector<Coordinate>,
+    pub candidates: CandidateSet,
+}
+
+impl AlmostLockedSet {
+    pub fn values(&self) -> Vector<u8> {
+        return self.candidates.iter().collect();
+    }
+
+    pub fn contains_value(&self, value: u8) -> bool {
+        return self.candidates.contains(value);
+    }
+
+    fn overlaps(&self, other: &AlmostLockedSet) -> bool {
+        return self.cells.iterate().any(|cell| other.cells.contains(cell));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlsPatternKind {
+    AlsXZ,
+    AlsXYWing,
+    DeathBlossom,
+}
+
+#[derive(Debug)]
+pub struct AlsPattern {
+    pub kind: AlsPatternKind,
+    pub sets: Vector<AlmostLockedSet>,
+    // Only a death blossom has a stem
+    pub stem: Option<Coordinate>,
+    pub eliminations: Vector<(Coordinate, u8)>,
+}
+
+// The candidates of every cell, and who sees whom, worked out once rather than inside every loop
+struct AlsSearch {
+    candidates: Vector<CandidateSet>,
+    peers: PeerTable,
+    coordinates: Vector<Coordinate>,
+    dimension: usize,
+}
+
+impl AlsSearch {
+    fn new(sudoku: &Puzzle) -> Self {
+        let coordinates = sudoku.cell_grid.coordinates();
+        let candidates = coordinates
+            .iterate()
+            .map(|&coordinate| sudoku.cell_grid[coordinate].potentially_valid_values)
+            .collect();
+
+        return Self {
+            candidates,
+            peers: PeerTable::new(sudoku),
+            coordinates,
+            dimension: sudoku.shape().dimension()
+        };
+    }
+
+    fn candidates_at(&self, coordinate: Coordinate) -> CandidateSet {
+        return self.candidates[coordinate.row * self.dimension + coordinate.column];
+    }
+
+    fn cells_with(&self, set: &AlmostLockedSet, value: u8) -> Vector<Coordinate> {
+        return set.cells.iterate().copied().filter(|&cell| self.candidates_at(cell).contains(value)).collect();
+    }
+
+    // A restricted common candidate is in both sets, with every cell holding it in one seeing every cell holding it in the other.
+    // At most one of the sets can then hold it, so the other is locked.
+    fn restricted_commons(&self, first: &AlmostLockedSet, second: &AlmostLockedSet) -> CandidateSet {
+
+        let mut restricted = CandidateSet::new();
+
+        for value in first.candidates & second.candidates {
+            let first_cells = self.cells_with(first, value);
+            let second_cells = self.cells_with(second, value);
+
+            if first_cells.iterate().all(|&cell| second_cells.iterate().all(|&other| self.peers.sees(cell, other))) {
+                restricted.insert(value);
+            }
+        }
+
+        return restricted;
+    }
+
+    // Once the value is known to be somewhere in these cells, every other cell seeing all of them loses it
+    fn eliminate_seeing_all(&self, value: u8, holders: &[Coordinate], excluded: &[Coordinate], eliminations: &mut Vector<(Coordinate, u8)>) {
+
+        if holders.is_empty() {
+            return;
+        }
+
+        for &coordinate in &self.coordinates {
+
+            if excluded.contains(&coordinate) || !self.candidates_at(coordinate).contains(value) {
+                continue;
+            }
+
+            if holders.iterate().all(|&holder| self.peers.sees(coordinate, holder)) && !eliminations.contains(&(coordinate, value)) {
+                eliminations.push((coordinate, value));
+            }
+        }
+    }
+}
+
+pub struct AlmostLockedSets;
+
+impl Technique for AlmostLockedSets {
+    fn name(&self) -> StringSlice<'static> {
+        return "Almost locked sets";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 65;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let patterns = find_als_patterns(sudoku);
+        let pattern_cells: Vector<_> = patterns
+            .iterate()
+            .flat_map(|pattern| pattern.stem.into_iter().chain(pattern.sets.iterate().flat_map(|set| set.cells.clone())))
+            .collect();
+
+        let step = Step::observe(self.name(), sudoku, |sudoku| eliminate_als_patterns(sudoku, patterns));
+        return StepResult::from(step.map(|step| step.with_cells(pattern_cells)));
+    }
+}
+
+fn eliminate_als_patterns(sudoku: &mut Puzzle, patterns: Vector<AlsPattern>) -> bool {
+
+    let mut any_eliminated = false;
+
+    for pattern in patterns {
+        for (coordinate, value) in pattern.eliminations {
+            any_eliminated = sudoku.cell_grid[coordinate].discount_value(value) || any_eliminated;
+        }
+    }
+
+    return any_eliminated;
+}
+
+// Only patterns that would eliminate something are returned
+pub fn find_als_patterns(sudoku: &Puzzle) -> Vector<AlsPattern> {
+
+    let search = AlsSearch::new(sudoku);
+    let sets = find_almost_locked_sets(sudoku, &search);
+
+    let mut patterns = find_als_xz(&search, &sets);
+    patterns.extend(find_als_xy_wings(&search, &sets));
+    patterns.extend(find_death_blossoms(sudoku, &search, &sets));
+    return patterns;
+}
+
+// Sets are found within each row, column and block. A set lying in both a row and a block is only given once.
+fn find_almost_locked_sets(sudoku: &Puzzle, search: &AlsSearch) -> Vector<AlmostLockedSet> {
+
+    let mut seen: HashSet<Vector<Coordinate>> = HashSet::new();
+    let mut sets = Vector::new();
+
+    for group in sudoku.rows.iterate().chain(sudoku.columns.iterate()).chain(sudoku.blocks.iterate()) {
+
+        let unsolved = group.unsolved(&sudoku.cell_grid);
+
+        // Every unsolved cell of the unit together is locked, not almost locked
+        let most_cells = (unsolved.len().saturating_sub(1)).min(LARGEST_ALMOST_LOCKED_SET);
+
+        let mut found = Vector::new();
+        grow_almost_locked_sets(search, &unsolved, most_cells, &mut Vector::new(), CandidateSet::new(), &mut found);
+
+        // Smallest first, as they are the easiest to follow
+        found.sort_by_key(|set: &AlmostLockedSet| set.cells.len());
+
+        for mut set in found {
+            set.cells.sort();
+            if seen.insert(set.cells.clone()) {
+                sets.push(set);
+            }
+        }
+    }
+
+    return sets;
+}
+
+// Adds cells one at a time, dropping any choice that already has more candidates than the largest set could hold.
+// Adding a cell never takes a candidate away, so nothing grown from it could be a set either.
+fn grow_almost_locked_sets(search: &AlsSearch, cells: &[Coordinate], most_cells: usize, chosen: &mut Vector<Coordinate>, candidates: CandidateSet, found: &mut Vector<AlmostLockedSet>) {
+
+    for (index, &cell) in cells.iterate().enumerate() {
+
+        let candidates = candidates | search.candidates_at(cell);
+        if candidates.len() > most_cells + 1 {
+            continue;
+        }
+
+        chosen.push(cell);
+
+        if candidates.len() == chosen.len() + 1 {
+            found.push(AlmostLockedSet {
+                cells: chosen.clone(),
+                candidates
+            });
+        }
+
+        if chosen.len() < most_cells {
+            grow_almost_locked_sets(search, &cells[index + 1..], most_cells, chosen, candidates, found);
+        }
+
+        chosen.pop();
+    }
+}
+
+// Two sets joined by a restricted common candidate X: one of them is locked, so any other value Z they share
+// is in one of them, and cells seeing every Z in both lose it. Joined by two restricted commons, both sets are locked.
+fn find_als_xz(search: &AlsSearch, sets: &[AlmostLockedSet]) -> Vector<AlsPattern> {
+
+    let mut patterns = Vector::new();
+
+    for (index, first) in sets.iterate().enumerate() {
+        for second in &sets[index + 1..] {
+
+            if (first.candidates & second.candidates).is_empty() || first.overlaps(second) {
+                continue;
+            }
+
+            let restricted = search.restricted_commons(first, second);
+            if restricted.is_empty() {
+                continue;
+            }
+
+            let both_cells: Vector<Coordinate> = first.cells.iterate().chain(second.cells.iterate()).copied().collect();
+            let mut eliminations = Vector::new();
+
+            for value in (first.candidates & second.candidates) - restricted {
+                let holders: Vector<Coordinate> = search.cells_with(first, value).into_iter().chain(search.cells_with(second, value)).collect();
+                search.eliminate_seeing_all(value, &holders, &both_cells, &mut eliminations);
+            }
+
+            if restricted.len() == 2 {
+                for value in restricted {
+                    let holders: Vector<Coordinate> = search.cells_with(first, value).into_iter().chain(search.cells_with(second, value)).collect();
+                    search.eliminate_seeing_all(value, &holders, &both_cells, &mut eliminations);
+                }
+
+                for set in [first, second] {
+                    for value in set.candidates - restricted {
+                        search.eliminate_seeing_all(value, &search.cells_with(set, value), &both_cells, &mut eliminations);
+                    }
+                }
+            }
+
+            if !eliminations.is_empty() {
+                patterns.push(AlsPattern {
+                    kind: AlsPatternKind::AlsXZ,
+                    sets: vec![first.clone(), second.clone()],
+                    stem: None,
+                    eliminations
+                });
+            }
+        }
+    }
+
+    return patterns;
+}
+
+// A pivot set joined to one wing by X and to the other by Y. If the pivot holds X the first wing is locked,
+// if it holds Y the second one is, and it can't hold both, so a value Z common to the wings is in one of them.
+fn find_als_xy_wings(search: &AlsSearch, sets: &[AlmostLockedSet]) -> Vector<AlsPattern> {
+
+    let mut patterns = Vector::new();
+
+    for pivot in sets {
+
+        let linked: Vector<(&AlmostLockedSet, CandidateSet)> = sets
+            .iterate()
+            .filter(|set| *set != pivot && !set.overlaps(pivot))
+            .map(|set| (set, search.restricted_commons(pivot, set)))
+            .filter(|(_, restricted)| !restricted.is_empty())
+            .collect();
+
+        for (index, &(first, first_restricted)) in linked.iterate().enumerate() {
+            for &(second, second_restricted) in &linked[index + 1..] {
+
+                if first.overlaps(second) {
+                    continue;
+                }
+
+                for x in first_restricted {
+                for y in second_restricted {
+                    if x == y {
+                        continue;
+                    }
+
+                    let wing_cells: Vector<Coordinate> = first.cells.iterate().chain(second.cells.iterate()).chain(pivot.cells.iterate()).copied().collect();
+                    let mut eliminations = Vector::new();
+
+                    for z in (first.candidates & second.candidates) - CandidateSet::from([x, y]) {
+                        let holders: Vector<Coordinate> = search.cells_with(first, z).into_iter().chain(search.cells_with(second, z)).collect();
+                        search.eliminate_seeing_all(z, &holders, &wing_cells, &mut eliminations);
+                    }
+
+                    if !eliminations.is_empty() {
+                        patterns.push(AlsPattern {
+                            kind: AlsPatternKind::AlsXYWing,
+                            sets: vec![pivot.clone(), first.clone(), second.clone()],
+                            stem: None,
+                            eliminations
+                        });
+                    }
+                }}
+            }
+        }
+    }
+
+    return patterns;
+}
+
+// A stem cell with a petal for each of its candidates, where every cell of a petal holding that candidate sees the stem.
+// Whatever the stem turns out to be, one petal is locked, so a value Z in every petal is in one of them.
+fn find_death_blossoms(sudoku: &Puzzle, search: &AlsSearch, sets: &[AlmostLockedSet]) -> Vector<AlsPattern> {
+
+    let mut patterns = Vector::new();
+
+    for stem in sudoku.cell_grid.coordinates() {
+
+        let stem_candidates = search.candidates_at(stem);
+        if !(2..=LARGEST_DEATH_BLOSSOM_STEM).contains(&stem_candidates.len()) {
+            continue;
+        }
+
+        let petals_for_value: Vector<Vector<&AlmostLockedSet>> = stem_candidates
+            .iter()
+            .map(|value| sets
+                .iterate()
+                .filter(|set| set.contains_value(value) && !set.cells.contains(&stem))
+                .filter(|set| search.cells_with(set, value).iterate().all(|&cell| search.peers.sees(cell, stem)))
+                .collect())
+            .collect();
+
+        let mut chosen = Vector::new();
+        add_petals(search, stem, &petals_for_value, CandidateSet::full(search.dimension as u8) - stem_candidates, &mut chosen, &mut patterns);
+    }
+
+    return patterns;
+}
+
+fn add_petals<'a>(search: &AlsSearch, stem: Coordinate, petals_for_value: &[Vector<&'a AlmostLockedSet>], common: CandidateSet, chosen: &mut Vector<&'a AlmostLockedSet>, patterns: &mut Vector<AlsPattern>) {
+
+    if common.is_empty() {
+        return;
+    }
+
+    let Some(petals) = petals_for_value.get(chosen.len()) else {
+
+        let mut blossom_cells: Vector<Coordinate> = chosen.iterate().flat_map(|petal| petal.cells.iterate().copied()).collect();
+        blossom_cells.push(stem);
+        let mut eliminations = Vector::new();
+
+        for z in common {
+            let holders: Vector<Coordinate> = chosen.iterate().flat_map(|petal| search.cells_with(petal, z)).collect();
+            search.eliminate_seeing_all(z, &holders, &blossom_cells, &mut eliminations);
+        }
+
+        if !eliminations.is_empty() {
+            patterns.push(AlsPattern {
+                kind: AlsPatternKind::DeathBlossom,
+                sets: chosen.iterate().map(|petal| (*petal).clone()).collect(),
+                stem: Some(stem),
+                eliminations
+            });
+        }
+        return;
+    };
+
+    for &petal in petals {
+        if chosen.iterate().any(|other| other.overlaps(petal)) {
+            continue;
+        }
+
+        chosen.push(petal);
+        add_petals(search, stem, petals_for_value, common & petal.candidates, chosen, patterns);
+        chosen.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::sudoku::{core::{consts::PUZZLE_MAXIMUM_VALUE, dancing_links::find_exact_cover_solutions, puzzle::SeedGrid, shape::Shape}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{backtracking_brute_force::find_solutions, implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    fn keep_only_candidates(sudoku: &mut Puzzle, coordinate: Coordinate, values: &[u8]) {
+        let discounted: Vector<u8> = (1..=PUZZLE_MAXIMUM_VALUE).filter(|value| !values.contains(value)).collect();
+        sudoku.cell_grid[coordinate].discount_values(discounted);
+    }
+
+    fn patterns_of_kind(sudoku: &Puzzle, kind: AlsPatternKind) -> Vector<AlsPattern> {
+        return find_als_patterns(sudoku).into_iter().filter(|pattern| pattern.kind == kind).collect();
+    }
+
+    #[test]
+    fn almost_locked_sets_include_bi_value_cells_and_larger_sets() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 4), &[2, 3]);
+
+        let sets = find_almost_locked_sets(&sudoku, &AlsSearch::new(&sudoku));
+
+        assert!(sets.contains(&AlmostLockedSet { cells: vec![Coordinate::new(0, 0)], candidates: CandidateSet::from([1, 2]) }));
+        assert!(sets.contains(&AlmostLockedSet { cells: vec![Coordinate::new(0, 0), Coordinate::new(0, 4)], candidates: CandidateSet::from([1, 2, 3]) }));
+        assert!(!sets.iterate().any(|set| set.cells == vec![Coordinate::new(1, 1)]));
+    }
+
+    #[test]
+    fn sets_sharing_a_row_and_block_are_only_given_once() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 1), &[2, 3]);
+
+        let sets = find_almost_locked_sets(&sudoku, &AlsSearch::new(&sudoku));
+
+        assert_eq!(sets.iterate().filter(|set| set.cells == vec![Coordinate::new(0, 0), Coordinate::new(0, 1)]).count(), 1);
+    }
+
+    #[test]
+    fn finds_sets_quickly_on_a_16x16_puzzle() {
+        // A solved grid with two cells in five kept as givens
+        let shape = Shape::new(4, 4).expect("test shape is valid");
+        let solution = find_exact_cover_solutions(shape, &vec![vec![None; 16]; 16], 1).remove(0);
+        let seed: SeedGrid = (0..16).map(|row| (0..16).map(|column| solution[row][column].filter(|_| (row * 7 + column * 13) % 5 < 2)).collect()).collect();
+        let mut sudoku = Puzzle::from_seed(shape, &seed);
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
+
+        let started = Instant::now();
+        let sets = find_almost_locked_sets(&sudoku, &AlsSearch::new(&sudoku));
+
+        assert!(!sets.is_empty());
+        assert!(sets.iterate().all(|set| set.cells.len() <= LARGEST_ALMOST_LOCKED_SET && set.candidates.len() == set.cells.len() + 1));
+        assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+    }
+
+    #[test]
+    fn als_xz_removes_shared_value_from_cells_seeing_it_in_both_sets() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 4), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(4, 4), &[2, 3]);
+
+        let patterns = patterns_of_kind(&sudoku, AlsPatternKind::AlsXZ);
+
+        assert!(patterns.iterate().any(|pattern|
+            pattern.sets[0].cells == vec![Coordinate::new(0, 0)]
+            && pattern.sets[1].cells == vec![Coordinate::new(0, 4), Coordinate::new(4, 4)]
+            && pattern.eliminations == vec![(Coordinate::new(4, 0), 2)]));
+    }
+
+    #[test]
+    fn als_xy_wing_removes_value_common_to_both_wings() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 4), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(4, 0), &[2, 3]);
+
+        let patterns = patterns_of_kind(&sudoku, AlsPatternKind::AlsXYWing);
+
+        assert!(patterns.iterate().any(|pattern|
+            pattern.sets[0].cells == vec![Coordinate::new(0, 0)]
+            && pattern.eliminations == vec![(Coordinate::new(4, 4), 3)]));
+
+        assert_ne!(AlmostLockedSets.apply(&mut sudoku), StepResult::NoProgress);
+        assert!(!sudoku.cell_grid[4][4].potentially_valid_values.contains(3));
+    }
+
+    #[test]
+    fn death_blossom_removes_value_common_to_every_petal() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2, 4]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 2), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(2, 0), &[2, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 1), &[3, 4]);
+
+        let patterns = patterns_of_kind(&sudoku, AlsPatternKind::DeathBlossom);
+
+        let blossom = patterns
+            .iterate()
+            .find(|pattern| pattern.sets.iterate().all(|petal| petal.cells.len() == 1))
+            .expect("the three bi-value cells are petals");
+        assert_eq!(blossom.stem, Some(Coordinate::new(0, 0)));
+        let mut eliminations = blossom.eliminations.clone();
+        eliminations.sort();
+        assert_eq!(eliminations, vec![(Coordinate::new(0, 1), 3), (Coordinate::new(1, 0), 3), (Coordinate::new(1, 2), 3), (Coordinate::new(2, 1), 3), (Coordinate::new(2, 2), 3)]);
+    }
+
+    #[test]
+    fn never_contradicts_the_solution() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let solution = find_solutions(&mut solved, 1).remove(0);
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(AlmostLockedSets)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        for coordinate in sudoku.cell_grid.coordinates() {
+            let cell = &sudoku.cell_grid[coordinate];
+            let expected = solution[coordinate.row][coordinate.column].expect("solution is complete");
+            assert!(cell.value == Some(expected) || cell.potentially_valid_values.contains(expected));
+        }
+    }
+}
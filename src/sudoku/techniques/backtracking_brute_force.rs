@@ -1,5 +1,150 @@
-use crate::sudoku::core::puzzle::Puzzle;
+use crate::pretty::aliases::*;
+use crate::sudoku::core::{cell::Cell, cell_grid::Coordinate, puzzle::{Puzzle, SeedGrid}, validatable_units::PuzzleValidator};
 
-pub fn solve_by_brute_force(sudoku: &mut Puzzle) {
+use super::{implicitly_solved::eliminate_options_from_groups, solver::{StepResult, Technique}, step::Step};
 
-}
\ No newline at end of file
+pub struct BruteForce;
+
+impl Technique for BruteForce {
+    fn name(&self) -> StringSlice<'static> {
+        return "Brute force";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 100;
+    }
+
+    // Fills in the whole grid at once when there is a solution, so it is only worth reaching for when nothing else works
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        return StepResult::from(Step::observe(self.name(), sudoku, |sudoku| !find_solutions(sudoku, 1).is_empty()));
+    }
+}
+
+// Leaves the puzzle holding the last solution found, or with only its candidates tidied up when there are none
+pub fn find_solutions(sudoku: &mut Puzzle, limit: usize) -> Vector<SeedGrid> {
+
+    let mut solutions = Vector::new();
+
+    // Guesses only ever respect the rows, columns and blocks, so clashing givens would never be noticed
+    if limit == 0 || !sudoku.is_valid() {
+        return solutions;
+    }
+
+    eliminate_used_values(sudoku);
+    search(sudoku, limit, &mut solutions);
+    return solutions;
+}
+
+// Returns true once enough solutions have been found, and the search should stop where it is
+fn search(sudoku: &mut Puzzle, limit: usize, solutions: &mut Vector<SeedGrid>) -> bool {
+
+    let Some(cell) = most_constrained_cell(sudoku) else {
+        if sudoku.is_complete() {
+            solutions.push(sudoku.to_seed());
+        }
+        return solutions.len() >= limit;
+    };
+
+    let candidates = sudoku.cell_grid[cell].potentially_valid_values;
+
+    // An empty cell that has run out of candidates means an earlier guess was wrong, and we fall straight through
+    for candidate in candidates {
+        let snapshot = snapshot_cells(sudoku);
+
+        sudoku.cell_grid[cell].set_value(candidate);
+        eliminate_used_values(sudoku);
+
+        if search(sudoku, limit, solutions) {
+            return true;
+        }
+
+        restore_cells(sudoku, &snapshot);
+    }
+
+    return false;
+}
+
+fn most_constrained_cell(sudoku: &Puzzle) -> Option<Coordinate> {
+    return sudoku.cell_grid
+        .coordinates()
+        .into_iter()
+        .filter(|&coordinate| sudoku.cell_grid[coordinate].value.is_none())
+        .min_by_key(|&coordinate| sudoku.cell_grid[coordinate].potentially_valid_values.len());
+}
+
+fn eliminate_used_values(sudoku: &mut Puzzle) {
+    eliminate_options_from_groups(&mut sudoku.cell_grid, &sudoku.rows);
+    eliminate_options_from_groups(&mut sudoku.cell_grid, &sudoku.columns);
+    eliminate_options_from_groups(&mut sudoku.cell_grid, &sudoku.blocks);
+}
+
+pub fn snapshot_cells(sudoku: &Puzzle) -> Vector<Cell> {
+    return sudoku.cell_grid.cells.clone();
+}
+
+pub fn restore_cells(sudoku: &mut Puzzle, snapshot: &[Cell]) {
+    sudoku.cell_grid.cells.clone_from_slice(snapshot);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{draw::terminal_print::draw_all_rows, format::serializer::Serializer};
+
+    use super::*;
+
+    #[test]
+    fn solves_puzzle_that_logical_techniques_cannot_finish() {
+        let test_case = "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let expected = "812753649943682175675491283154237896369845721287169534521974368438526917796318452";
+        let serializer = Serializer::new();
+        let mut sudoku = serializer.new_puzzle(test_case).expect("test data is valid");
+
+        let solved = !find_solutions(&mut sudoku, 1).is_empty();
+        draw_all_rows(&sudoku);
+
+        assert!(solved);
+        assert!(sudoku.is_complete());
+        assert_eq!(serializer.serialize(&sudoku), expected);
+    }
+
+    #[test]
+    fn leaves_completed_puzzle_as_it_is() {
+        let test_case = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+        let serializer = Serializer::new();
+        let mut sudoku = serializer.new_puzzle(test_case).expect("test data is valid");
+
+        assert_eq!(find_solutions(&mut sudoku, 1).len(), 1);
+        assert_eq!(serializer.serialize(&sudoku), test_case);
+    }
+
+    #[test]
+    fn reports_no_solution_when_givens_contradict() {
+        // The top left cell can be neither 1-8 from its row, nor 9 from its column
+        let test_case = ".123456789.......................................................................";
+        let serializer = Serializer::new();
+        let mut sudoku = serializer.new_puzzle(test_case).expect("test data is valid");
+
+        assert_eq!(BruteForce.apply(&mut sudoku), StepResult::NoProgress);
+        assert!(sudoku.cell_grid[0][0].value.is_none());
+    }
+
+    #[test]
+    fn reports_no_solution_when_givens_repeat_in_a_unit() {
+        let test_case = "1.......1........................................................................";
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+
+        assert_eq!(BruteForce.apply(&mut sudoku), StepResult::NoProgress);
+    }
+
+    #[test]
+    fn find_solutions_stops_at_limit() {
+        let empty = ".".repeat(81);
+        let mut sudoku = Serializer::new().new_puzzle(&empty).expect("test data is valid");
+
+        let solutions = find_solutions(&mut sudoku, 3);
+
+        assert_eq!(solutions.len(), 3);
+        assert_ne!(solutions[0], solutions[1]);
+        assert_ne!(solutions[1], solutions[2]);
+    }
+}
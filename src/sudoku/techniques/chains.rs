@@ -0,0 +1,498 @@
+use crate::pretty::aliases::*;
+use std::collections::{HashMap, VecDeque};
+
+use crate::sudoku::core::{cell_grid::Coordinate, puzzle::{PeerTable, Puzzle}};
+
+use super::{coloring::conjugate_pairs_in, solver::{StepResult, Technique}, step::Step};
+
+// Anything shorter than three links is a single strong link, which the intersection techniques already cover
+const SHORTEST_CHAIN_LINKS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Candidate {
+    pub coordinate: Coordinate,
+    pub value: u8
+}
+
+impl Candidate {
+    pub fn new(coordinate: Coordinate, value: u8) -> Self {
+        Self {
+            coordinate,
+            value
+        }
+    }
+}
+
+// A strong link means at least one end holds its value, a weak link that at most one does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Strong,
+    Weak,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainKind {
+    XChain,
+    XCycle,
+    XYChain,
+    AlternatingInferenceChain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainShape {
+    // The two ends can't both be false, so anything both ends rule out goes
+    Open,
+    // The last node links weakly back to the first, which turns every weak link in the loop strong
+    ContinuousLoop,
+    // The chain comes back round to where it started on a strong link, so the first node must be true
+    DiscontinuousLoop,
+}
+
+#[derive(Debug)]
+pub struct Chain {
+    pub kind: ChainKind,
+    pub shape: ChainShape,
+    pub nodes: Vector<Candidate>,
+    // links[i] joins nodes[i] and nodes[i + 1]
+    pub links: Vector<LinkKind>,
+    pub eliminations: Vector<Candidate>,
+    pub placements: Vector<Candidate>,
+}
+
+pub struct LinkGraph {
+    strong: HashMap<Candidate, Vector<Candidate>>,
+    weak: HashMap<Candidate, Vector<Candidate>>,
+    peers: PeerTable,
+}
+
+impl LinkGraph {
+    pub fn build(sudoku: &Puzzle) -> Self {
+
+        let peers = PeerTable::new(sudoku);
+        let candidates: Vector<Candidate> = sudoku.cell_grid
+            .coordinates()
+            .into_iter()
+            .flat_map(|coordinate| sudoku.cell_grid[coordinate].potentially_valid_values.iter().map(|value| Candidate::new(coordinate, value)).collect::<Vector<_>>())
+            .collect();
+
+        let mut strong: HashMap<Candidate, Vector<Candidate>> = HashMap::new();
+        let mut weak: HashMap<Candidate, Vector<Candidate>> = HashMap::new();
+
+        // Bi-value cells: if it isn't one value, it's the other
+        for coordinate in sudoku.cell_grid.coordinates() {
+            let values: Vector<u8> = sudoku.cell_grid[coordinate].potentially_valid_values.iter().collect();
+            if values.len() == 2 {
+                add_link(&mut strong, Candidate::new(coordinate, values[0]), Candidate::new(coordinate, values[1]));
+            }
+        }
+
+        // Conjugate pairs: if the value isn't in one cell of the unit, it's in the other
+        for value in sudoku.shape().values() {
+            for groups in [&sudoku.rows, &sudoku.columns, &sudoku.blocks] {
+                for (first, second) in conjugate_pairs_in(sudoku, groups, value) {
+                    add_link(&mut strong, Candidate::new(first, value), Candidate::new(second, value));
+                }
+            }
+        }
+
+        // Two values in one cell, or one value in two cells that see each other, can't both be true
+        for (index, &first) in candidates.iterate().enumerate() {
+            for &second in &candidates[index + 1..] {
+                let same_cell = first.coordinate == second.coordinate;
+                let same_value_seen = first.value == second.value && peers.sees(first.coordinate, second.coordinate);
+
+                if same_cell || same_value_seen {
+                    add_link(&mut weak, first, second);
+                }
+            }
+        }
+
+        return Self {
+            strong,
+            weak,
+            peers
+        };
+    }
+
+    pub fn is_strong(&self, first: Candidate, second: Candidate) -> bool {
+        return self.strong.get(&first).is_some_and(|linked| linked.contains(&second));
+    }
+
+    pub fn is_weak(&self, first: Candidate, second: Candidate) -> bool {
+        return self.weak.get(&first).is_some_and(|linked| linked.contains(&second));
+    }
+
+    fn sees(&self, first: Coordinate, second: Coordinate) -> bool {
+        return self.peers.sees(first, second);
+    }
+
+    fn linked(&self, candidate: Candidate, kind: LinkKind) -> &[Candidate] {
+        let links = match kind {
+            LinkKind::Strong => &self.strong,
+            LinkKind::Weak => &self.weak,
+        };
+        return links.get(&candidate).map(|linked| linked.as_slice()).unwrap_or(&[]);
+    }
+}
+
+pub struct Chains;
+
+impl Technique for Chains {
+    fn name(&self) -> StringSlice<'static> {
+        return "Chains";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 60;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let Some(chain) = find_chain(sudoku) else {
+            return StepResult::NoProgress;
+        };
+
+        let chain_cells: Vector<_> = chain.nodes.iterate().map(|node| node.coordinate).collect();
+        let step = Step::observe(self.name(), sudoku, |sudoku| apply_chain(sudoku, &chain));
+        return StepResult::from(step.map(|step| step.with_cells(chain_cells)));
+    }
+}
+
+fn apply_chain(sudoku: &mut Puzzle, chain: &Chain) -> bool {
+
+    for candidate in &chain.eliminations {
+        sudoku.cell_grid[candidate.coordinate].discount_value(candidate.value);
+    }
+
+    for candidate in &chain.placements {
+        sudoku.cell_grid[candidate.coordinate].set_value(candidate.value);
+    }
+
+    return true;
+}
+
+// Gives the shortest chain that eliminates or places anything, as that is the easiest one for a person to follow
+pub fn find_chain(sudoku: &Puzzle) -> Option<Chain> {
+
+    let graph = LinkGraph::build(sudoku);
+    let mut starts: Vector<Candidate> = graph.strong.keys().copied().collect();
+    starts.sort();
+
+    let mut shortest: Option<Chain> = None;
+
+    for start in starts {
+        if let Some(chain) = shortest_chain_from(sudoku, &graph, start) {
+            if shortest.as_ref().is_none_or(|found| chain.nodes.len() < found.nodes.len()) {
+                shortest = Some(chain);
+            }
+        }
+    }
+
+    return shortest;
+}
+
+// Assume the start is false and follow what that forces: across a strong link the next candidate must be true,
+// and across a weak link from a true candidate the next must be false. Reaching a candidate forced true means
+// that either the start or that candidate is true.
+fn shortest_chain_from(sudoku: &Puzzle, graph: &LinkGraph, start: Candidate) -> Option<Chain> {
+
+    let mut parents: HashMap<(Candidate, bool), (Candidate, bool)> = HashMap::new();
+    let mut to_visit: VecDeque<(Candidate, bool)> = VecDeque::from([(start, false)]);
+
+    while let Some((candidate, is_true)) = to_visit.pop_front() {
+
+        let link_kind = if is_true { LinkKind::Weak } else { LinkKind::Strong };
+
+        for &next in graph.linked(candidate, link_kind) {
+
+            let state = (next, !is_true);
+            if parents.contains_key(&state) || state == (start, false) {
+                continue;
+            }
+            parents.insert(state, (candidate, is_true));
+
+            if !is_true {
+                let nodes = path_to(&parents, start, next);
+                if let Some(chain) = chain_from_nodes(sudoku, graph, nodes) {
+                    return Some(chain);
+                }
+                if next == start {
+                    continue;
+                }
+            }
+
+            to_visit.push_back(state);
+        }
+    }
+
+    return None;
+}
+
+fn path_to(parents: &HashMap<(Candidate, bool), (Candidate, bool)>, start: Candidate, end: Candidate) -> Vector<Candidate> {
+
+    let mut nodes = vec![end];
+    let mut state = (end, true);
+
+    while let Some(&parent) = parents.get(&state) {
+        nodes.push(parent.0);
+        if parent == (start, false) {
+            break;
+        }
+        state = parent;
+    }
+
+    nodes.reverse();
+    return nodes;
+}
+
+fn chain_from_nodes(sudoku: &Puzzle, graph: &LinkGraph, nodes: Vector<Candidate>) -> Option<Chain> {
+
+    let link_count = nodes.len() - 1;
+    let start = nodes[0];
+    let end = nodes[link_count];
+
+    // A candidate both forced true and false along the way is a contradiction of its own, not a chain
+    let inner_nodes = if start == end { &nodes[..link_count] } else { &nodes[..] };
+    let mut distinct = inner_nodes.to_vec();
+    distinct.sort();
+    distinct.dedup();
+
+    if link_count < SHORTEST_CHAIN_LINKS || distinct.len() != inner_nodes.len() {
+        return None;
+    }
+
+    let links: Vector<LinkKind> = (0..link_count).map(|index| if index % 2 == 0 { LinkKind::Strong } else { LinkKind::Weak }).collect();
+
+    let (shape, eliminations, placements) = if start == end {
+        (ChainShape::DiscontinuousLoop, vec![], vec![start])
+    } else if graph.is_weak(end, start) {
+        (ChainShape::ContinuousLoop, continuous_loop_eliminations(sudoku, graph, &nodes, &links), vec![])
+    } else {
+        (ChainShape::Open, open_chain_eliminations(sudoku, graph, start, end), vec![])
+    };
+
+    if eliminations.is_empty() && placements.is_empty() {
+        return None;
+    }
+
+    return Some(Chain {
+        kind: chain_kind(&nodes, &links, shape),
+        shape,
+        nodes,
+        links,
+        eliminations,
+        placements
+    });
+}
+
+fn open_chain_eliminations(sudoku: &Puzzle, graph: &LinkGraph, start: Candidate, end: Candidate) -> Vector<Candidate> {
+
+    // Both ends in one cell: the cell holds one of the two
+    if start.coordinate == end.coordinate {
+        return candidates_at(sudoku, start.coordinate)
+            .into_iter()
+            .filter(|candidate| candidate.value != start.value && candidate.value != end.value)
+            .collect();
+    }
+
+    // Both ends the same value: nothing seeing both can hold it
+    if start.value == end.value {
+        return candidates_with_value_seeing(sudoku, graph, start.value, &[start.coordinate, end.coordinate]);
+    }
+
+    // Different values in cells that see each other: each end rules its value out of the other's cell
+    if graph.sees(start.coordinate, end.coordinate) {
+        return [Candidate::new(end.coordinate, start.value), Candidate::new(start.coordinate, end.value)]
+            .into_iter()
+            .filter(|candidate| has_candidate(sudoku, *candidate))
+            .collect();
+    }
+
+    return vec![];
+}
+
+fn continuous_loop_eliminations(sudoku: &Puzzle, graph: &LinkGraph, nodes: &[Candidate], links: &[LinkKind]) -> Vector<Candidate> {
+
+    let mut weak_links: Vector<(Candidate, Candidate)> = links
+        .iterate()
+        .enumerate()
+        .filter(|(_, kind)| **kind == LinkKind::Weak)
+        .map(|(index, _)| (nodes[index], nodes[index + 1]))
+        .collect();
+    weak_links.push((nodes[nodes.len() - 1], nodes[0]));
+
+    let mut eliminations: Vector<Candidate> = Vector::new();
+
+    for (first, second) in weak_links {
+        let removed = if first.coordinate == second.coordinate {
+            candidates_at(sudoku, first.coordinate)
+                .into_iter()
+                .filter(|candidate| candidate.value != first.value && candidate.value != second.value)
+                .collect()
+        } else {
+            candidates_with_value_seeing(sudoku, graph, first.value, &[first.coordinate, second.coordinate])
+        };
+
+        for candidate in removed {
+            if !nodes.contains(&candidate) && !eliminations.contains(&candidate) {
+                eliminations.push(candidate);
+            }
+        }
+    }
+
+    return eliminations;
+}
+
+fn chain_kind(nodes: &[Candidate], links: &[LinkKind], shape: ChainShape) -> ChainKind {
+
+    if nodes.iterate().all(|node| node.value == nodes[0].value) {
+        return if shape == ChainShape::Open { ChainKind::XChain } else { ChainKind::XCycle };
+    }
+
+    // Every strong link inside a bi-value cell, and every weak link between cells on the same value
+    let is_xy_chain = shape == ChainShape::Open && links.iterate().enumerate().all(|(index, kind)| {
+        let (first, second) = (nodes[index], nodes[index + 1]);
+        match kind {
+            LinkKind::Strong => first.coordinate == second.coordinate,
+            LinkKind::Weak => first.value == second.value,
+        }
+    });
+
+    return if is_xy_chain { ChainKind::XYChain } else { ChainKind::AlternatingInferenceChain };
+}
+
+fn candidates_with_value_seeing(sudoku: &Puzzle, graph: &LinkGraph, value: u8, must_see: &[Coordinate]) -> Vector<Candidate> {
+    return sudoku.cell_grid
+        .coordinates()
+        .into_iter()
+        .filter(|coordinate| !must_see.contains(coordinate) && must_see.iterate().all(|&other| graph.sees(*coordinate, other)))
+        .map(|coordinate| Candidate::new(coordinate, value))
+        .filter(|&candidate| has_candidate(sudoku, candidate))
+        .collect();
+}
+
+fn candidates_at(sudoku: &Puzzle, coordinate: Coordinate) -> Vector<Candidate> {
+    return sudoku.cell_grid[coordinate].potentially_valid_values.iter().map(|value| Candidate::new(coordinate, value)).collect();
+}
+
+fn has_candidate(sudoku: &Puzzle, candidate: Candidate) -> bool {
+    return sudoku.cell_grid[candidate.coordinate].potentially_valid_values.contains(candidate.value);
+}
+
+fn add_link(links: &mut HashMap<Candidate, Vector<Candidate>>, first: Candidate, second: Candidate) {
+    for (from, to) in [(first, second), (second, first)] {
+        let linked = links.entry(from).or_default();
+        if !linked.contains(&to) {
+            linked.push(to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::consts::{PUZZLE_DIMENTION, PUZZLE_MAXIMUM_VALUE}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{backtracking_brute_force::find_solutions, implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    fn keep_only_candidates(sudoku: &mut Puzzle, coordinate: Coordinate, values: &[u8]) {
+        let discounted: Vector<u8> = (1..=PUZZLE_MAXIMUM_VALUE).filter(|value| !values.contains(value)).collect();
+        sudoku.cell_grid[coordinate].discount_values(discounted);
+    }
+
+    fn keep_value_in_row_only_at(sudoku: &mut Puzzle, row: usize, value: u8, columns: &[usize]) {
+        (0..PUZZLE_DIMENTION).filter(|column| !columns.contains(column)).for_each(|column| {sudoku.cell_grid[row][column].discount_value(value);});
+    }
+
+    fn keep_value_in_column_only_at(sudoku: &mut Puzzle, column: usize, value: u8, rows: &[usize]) {
+        (0..PUZZLE_DIMENTION).filter(|row| !rows.contains(row)).for_each(|row| {sudoku.cell_grid[row][column].discount_value(value);});
+    }
+
+    #[test]
+    fn link_graph_has_strong_links_for_bi_value_cells_and_conjugate_pairs() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_value_in_row_only_at(&mut sudoku, 4, 7, &[3, 8]);
+
+        let graph = LinkGraph::build(&sudoku);
+
+        assert!(graph.is_strong(Candidate::new(Coordinate::new(0, 0), 1), Candidate::new(Coordinate::new(0, 0), 2)));
+        assert!(graph.is_strong(Candidate::new(Coordinate::new(4, 8), 7), Candidate::new(Coordinate::new(4, 3), 7)));
+        assert!(!graph.is_strong(Candidate::new(Coordinate::new(1, 1), 1), Candidate::new(Coordinate::new(1, 1), 2)));
+        assert!(graph.is_weak(Candidate::new(Coordinate::new(1, 1), 1), Candidate::new(Coordinate::new(1, 1), 2)));
+        assert!(graph.is_weak(Candidate::new(Coordinate::new(1, 1), 5), Candidate::new(Coordinate::new(1, 7), 5)));
+        assert!(!graph.is_weak(Candidate::new(Coordinate::new(1, 1), 5), Candidate::new(Coordinate::new(7, 7), 5)));
+    }
+
+    #[test]
+    fn xy_chain_removes_end_value_from_cells_seeing_both_ends() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 4), &[2, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(4, 4), &[3, 1]);
+
+        let chain = find_chain(&sudoku).expect("the three bi-value cells make a chain");
+
+        assert_eq!(chain.kind, ChainKind::XYChain);
+        assert_eq!(chain.shape, ChainShape::Open);
+        assert_eq!(chain.nodes.len(), 6);
+        assert_eq!(chain.eliminations, vec![Candidate::new(Coordinate::new(4, 0), 1)]);
+    }
+
+    #[test]
+    fn x_cycle_continuous_loop_removes_value_along_its_weak_links() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 1, 5, &[2, 7]);
+        keep_value_in_row_only_at(&mut sudoku, 4, 5, &[2, 7]);
+
+        let chain = find_chain(&sudoku).expect("the two rows make a loop");
+
+        assert_eq!(chain.kind, ChainKind::XCycle);
+        assert_eq!(chain.shape, ChainShape::ContinuousLoop);
+        assert_eq!(chain.links, vec![LinkKind::Strong, LinkKind::Weak, LinkKind::Strong]);
+        assert!(chain.eliminations.contains(&Candidate::new(Coordinate::new(0, 2), 5)));
+        assert!(chain.eliminations.contains(&Candidate::new(Coordinate::new(8, 7), 5)));
+        assert_eq!(chain.eliminations.len(), 14);
+
+        assert_ne!(Chains.apply(&mut sudoku), StepResult::NoProgress);
+        assert!(!sudoku.cell_grid[6][2].potentially_valid_values.contains(5));
+    }
+
+    #[test]
+    fn discontinuous_loop_places_value_at_start() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[0, 1]);
+        keep_value_in_column_only_at(&mut sudoku, 0, 1, &[0, 1]);
+
+        let chain = find_chain(&sudoku).expect("the start cell is forced both ways");
+
+        assert_eq!(chain.shape, ChainShape::DiscontinuousLoop);
+        assert_eq!(chain.placements, vec![Candidate::new(Coordinate::new(0, 0), 1)]);
+
+        assert_ne!(Chains.apply(&mut sudoku), StepResult::NoProgress);
+        assert_eq!(sudoku.cell_grid[0][0].value, Some(1));
+    }
+
+    #[test]
+    fn chains_never_contradict_the_solution() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let solution = find_solutions(&mut solved, 1).remove(0);
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(Chains)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        for coordinate in sudoku.cell_grid.coordinates() {
+            let cell = &sudoku.cell_grid[coordinate];
+            let expected = solution[coordinate.row][coordinate.column].expect("solution is complete");
+            assert!(cell.value == Some(expected) || cell.potentially_valid_values.contains(expected));
+        }
+    }
+
+    #[test]
+    fn no_chain_without_strong_links() {
+        let sudoku = Puzzle::default();
+
+        assert!(find_chain(&sudoku).is_none());
+    }
+}
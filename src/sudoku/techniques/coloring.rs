@@ -0,0 +1,282 @@
+use crate::pretty::{aliases::*, iterable::*};
+
+use crate::sudoku::core::{cell_grid::Coordinate, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::Step};
+
+// Cells joined by a chain of conjugate pairs, split by which of the two alternating colors they got.
+// Exactly one of the two colors holds the value, though we don't yet know which.
+pub struct Cluster {
+    pub colors: [Vector<Coordinate>; 2]
+}
+
+impl Cluster {
+    fn contains(&self, coordinate: &Coordinate) -> bool {
+        return self.colors.iterate().any(|color| color.contains(coordinate));
+    }
+}
+
+pub struct Coloring;
+
+impl Technique for Coloring {
+    fn name(&self) -> StringSlice<'static> {
+        return "Coloring";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 46;
+    }
+
+    // A value at a time, as that is all the clusters can say anything about
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        for value in sudoku.shape().values() {
+            let clusters = build_clusters(sudoku, value);
+            let step = Step::observe(self.name(), sudoku, |sudoku| try_simple_coloring(sudoku, value, &clusters) || try_multi_coloring(sudoku, value, &clusters));
+
+            if let Some(step) = step {
+                let cluster_cells: Vector<_> = clusters.iterate().flat_map(|cluster| cluster.colors.iterate().flatten().copied()).collect();
+                return StepResult::Progressed(step.with_cells(cluster_cells));
+            }
+        }
+
+        return StepResult::NoProgress;
+    }
+}
+
+// A conjugate pair is the only two cells in a row, column or block that could hold the value
+pub fn conjugate_pairs(sudoku: &Puzzle, value: u8) -> Vector<(Coordinate, Coordinate)> {
+
+    let mut pairs = Vector::new();
+
+    for groups in [&sudoku.rows, &sudoku.columns, &sudoku.blocks] {
+        for pair in conjugate_pairs_in(sudoku, groups, value) {
+            // A pair within a block is often also the pair within its row or column
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+    }
+
+    return pairs;
+}
+
+pub fn conjugate_pairs_in(sudoku: &Puzzle, groups: &[CellGroup], value: u8) -> Vector<(Coordinate, Coordinate)> {
+    return groups
+        .iterate()
+        .map(|group| group.positions_with_candidate(&sudoku.cell_grid, value).iterate().map(|&position| group.cells[position]).collect::<Vector<_>>())
+        .filter(|coordinates| coordinates.len() == 2)
+        .map(|coordinates| (coordinates[0], coordinates[1]))
+        .collect();
+}
+
+pub fn build_clusters(sudoku: &Puzzle, value: u8) -> Vector<Cluster> {
+
+    let pairs = conjugate_pairs(sudoku, value);
+    let mut clusters: Vector<Cluster> = Vector::new();
+
+    for &(start, _) in &pairs {
+
+        if clusters.iterate().any(|cluster| cluster.contains(&start)) {
+            continue;
+        }
+
+        let mut cluster = Cluster { colors: [vec![start], vec![]] };
+        let mut to_visit = vec![(start, 0)];
+
+        while let Some((coordinate, color)) = to_visit.pop() {
+            for &(first, second) in &pairs {
+                let neighbour = if first == coordinate { second } else if second == coordinate { first } else { continue };
+
+                if cluster.contains(&neighbour) {
+                    continue;
+                }
+
+                cluster.colors[1 - color].push(neighbour);
+                to_visit.push((neighbour, 1 - color));
+            }
+        }
+
+        clusters.push(cluster);
+    }
+
+    return clusters;
+}
+
+fn try_simple_coloring(sudoku: &mut Puzzle, value: u8, clusters: &[Cluster]) -> bool {
+
+    let mut any_changed = false;
+
+    for cluster in clusters {
+
+        // Color wrap: two cells of one color see each other, so that color can't be the one holding the value
+        if let Some(false_color) = (0..2).find(|&color| any_see_each_other(sudoku, &cluster.colors[color], &cluster.colors[color])) {
+            any_changed = color_is_false(sudoku, value, cluster, false_color) || any_changed;
+            continue;
+        }
+
+        // Color trap: a cell seeing both colors loses the value, whichever color holds it
+        for coordinate in sudoku.cell_grid.coordinates() {
+            if cluster.contains(&coordinate) || !has_candidate(sudoku, coordinate, value) {
+                continue;
+            }
+
+            if sees_any(sudoku, coordinate, &cluster.colors[0]) && sees_any(sudoku, coordinate, &cluster.colors[1]) {
+                any_changed = sudoku.cell_grid[coordinate].discount_value(value) || any_changed;
+            }
+        }
+    }
+
+    return any_changed;
+}
+
+// With two clusters A and B, if a cell of color A1 sees a cell of color B1, those can't both hold the value,
+// so at least one of A2 or B2 must, and any cell seeing both A2 and B2 loses the value.
+// If A1 sees both colors of B, A1 can't hold the value at all.
+fn try_multi_coloring(sudoku: &mut Puzzle, value: u8, clusters: &[Cluster]) -> bool {
+
+    let mut any_changed = false;
+
+    for pair in clusters.iterate().collect::<Vector<_>>().combinations_of_size(2) {
+    for (first, second) in [(pair[0], pair[1]), (pair[1], pair[0])] {
+    for first_color in 0..2 {
+
+        if any_see_each_other(sudoku, &first.colors[first_color], &second.colors[0])
+        && any_see_each_other(sudoku, &first.colors[first_color], &second.colors[1]) {
+            return color_is_false(sudoku, value, first, first_color);
+        }
+
+        for second_color in 0..2 {
+
+            if !any_see_each_other(sudoku, &first.colors[first_color], &second.colors[second_color]) {
+                continue;
+            }
+
+            for coordinate in sudoku.cell_grid.coordinates() {
+                if first.contains(&coordinate) || second.contains(&coordinate) || !has_candidate(sudoku, coordinate, value) {
+                    continue;
+                }
+
+                if sees_any(sudoku, coordinate, &first.colors[1 - first_color]) && sees_any(sudoku, coordinate, &second.colors[1 - second_color]) {
+                    any_changed = sudoku.cell_grid[coordinate].discount_value(value) || any_changed;
+                }
+            }
+        }
+    }}}
+
+    return any_changed;
+}
+
+fn color_is_false(sudoku: &mut Puzzle, value: u8, cluster: &Cluster, false_color: usize) -> bool {
+
+    for &coordinate in &cluster.colors[false_color] {
+        sudoku.cell_grid[coordinate].discount_value(value);
+    }
+
+    for &coordinate in &cluster.colors[1 - false_color] {
+        sudoku.cell_grid[coordinate].set_value(value);
+    }
+
+    return true;
+}
+
+fn any_see_each_other(sudoku: &Puzzle, some_cells: &[Coordinate], other_cells: &[Coordinate]) -> bool {
+    return some_cells.iterate().any(|&coordinate| sees_any(sudoku, coordinate, other_cells));
+}
+
+fn sees_any(sudoku: &Puzzle, coordinate: Coordinate, others: &[Coordinate]) -> bool {
+    return others.iterate().any(|&other| sudoku.sees(coordinate, other));
+}
+
+fn has_candidate(sudoku: &Puzzle, coordinate: Coordinate, value: u8) -> bool {
+    return sudoku.cell_grid[coordinate].potentially_valid_values.contains(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::core::consts::PUZZLE_DIMENTION;
+
+    use super::*;
+
+    fn keep_value_in_row_only_at(sudoku: &mut Puzzle, row: usize, value: u8, columns: &[usize]) {
+        (0..PUZZLE_DIMENTION).filter(|column| !columns.contains(column)).for_each(|column| {sudoku.cell_grid[row][column].discount_value(value);});
+    }
+
+    fn keep_value_in_column_only_at(sudoku: &mut Puzzle, column: usize, value: u8, rows: &[usize]) {
+        (0..PUZZLE_DIMENTION).filter(|row| !rows.contains(row)).for_each(|row| {sudoku.cell_grid[row][column].discount_value(value);});
+    }
+
+    #[test]
+    fn build_clusters_alternates_colors_along_chain() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[0, 4]);
+        keep_value_in_column_only_at(&mut sudoku, 4, 1, &[0, 4]);
+
+        let clusters = build_clusters(&sudoku, 1);
+
+        assert_eq!(clusters.len(), 1);
+        let colors = &clusters[0].colors;
+        let (own_color, other_color) = if colors[0].contains(&Coordinate::new(0, 4)) { (0, 1) } else { (1, 0) };
+        assert_eq!(colors[own_color], vec![Coordinate::new(0, 4)]);
+        assert!(colors[other_color].contains(&Coordinate::new(0, 0)));
+        assert!(colors[other_color].contains(&Coordinate::new(4, 4)));
+    }
+
+    #[test]
+    fn color_trap_removes_value_from_cells_seeing_both_colors() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[0, 4]);
+        keep_value_in_column_only_at(&mut sudoku, 4, 1, &[0, 4]);
+        keep_value_in_row_only_at(&mut sudoku, 4, 1, &[1, 4]);
+
+        assert_ne!(Coloring.apply(&mut sudoku), StepResult::NoProgress);
+
+        assert!(!has_candidate(&sudoku, Coordinate::new(1, 1), 1));
+        assert!(!has_candidate(&sudoku, Coordinate::new(3, 0), 1));
+        assert!(has_candidate(&sudoku, Coordinate::new(8, 8), 1));
+        assert!(has_candidate(&sudoku, Coordinate::new(4, 1), 1));
+    }
+
+    #[test]
+    fn color_wrap_places_value_in_cells_of_other_color() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[0, 4]);
+        keep_value_in_column_only_at(&mut sudoku, 4, 1, &[0, 4]);
+        keep_value_in_row_only_at(&mut sudoku, 4, 1, &[1, 4]);
+        keep_value_in_column_only_at(&mut sudoku, 1, 1, &[1, 4]);
+
+        assert_ne!(Coloring.apply(&mut sudoku), StepResult::NoProgress);
+
+        assert_eq!(sudoku.cell_grid[0][4].value, Some(1));
+        assert_eq!(sudoku.cell_grid[4][1].value, Some(1));
+        assert!(!has_candidate(&sudoku, Coordinate::new(0, 0), 1));
+        assert!(!has_candidate(&sudoku, Coordinate::new(4, 4), 1));
+        assert!(!has_candidate(&sudoku, Coordinate::new(1, 1), 1));
+    }
+
+    #[test]
+    fn multi_coloring_removes_value_from_cells_seeing_both_other_colors() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[0, 3]);
+        keep_value_in_column_only_at(&mut sudoku, 1, 1, &[1, 5]);
+
+        assert_ne!(Coloring.apply(&mut sudoku), StepResult::NoProgress);
+
+        assert!(!has_candidate(&sudoku, Coordinate::new(5, 3), 1));
+        assert!(has_candidate(&sudoku, Coordinate::new(5, 4), 1));
+        assert!(has_candidate(&sudoku, Coordinate::new(2, 3), 1));
+    }
+
+    #[test]
+    fn multi_coloring_places_value_when_color_sees_both_colors_of_other_cluster() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[0, 6]);
+        keep_value_in_column_only_at(&mut sudoku, 6, 1, &[0, 5]);
+        keep_value_in_column_only_at(&mut sudoku, 1, 1, &[1, 5]);
+
+        assert_ne!(Coloring.apply(&mut sudoku), StepResult::NoProgress);
+
+        assert_eq!(sudoku.cell_grid[0][6].value, Some(1));
+        assert!(!has_candidate(&sudoku, Coordinate::new(0, 0), 1));
+        assert!(!has_candidate(&sudoku, Coordinate::new(5, 6), 1));
+    }
+}
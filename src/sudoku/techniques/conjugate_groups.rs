@@ -1,145 +1,115 @@
-use crate::{pretty::{aliases::*, iterable::*},  sudoku::{core::consts::PUZZLE_TOTAL_CELL_COUNT}};
+use crate::pretty::aliases::*;
 use std::collections::HashMap;
 
-use crate::sudoku::core::{puzzle::Puzzle, validatable_units::CellGroup};
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::CellGrid, puzzle::Puzzle, validatable_units::CellGroup};
 
-use super::implicitly_solved::{self, solve_single_candidate_cells};
+use super::{solver::{StepResult, Technique}, step::{Snapshot, Step, Unit}};
 
-pub fn solve_conjugate_groups(sudoku: &mut Puzzle) {
+pub struct ClosedCandidateGroups;
 
-    solve_single_candidate_cells(sudoku);
-    sudoku.cell_grid.grid.iterate().flat_map(|group| group).for_each(|cell| cell.borrow_mut().potentially_valid_values.sort());
-
-    let mut i = 0;
-    loop {
-        i+=1;
+impl Technique for ClosedCandidateGroups {
+    fn name(&self) -> StringSlice<'static> {
+        return "Closed candidate groups";
+    }
 
-        let any_eliminated = try_eliminate_closed_candidate_groups(sudoku);
+    fn difficulty(&self) -> u32 {
+        return 30;
+    }
 
-        if !any_eliminated {
-            println!("Finished eliminating candidates from closed groups after {} iterations", i);
-            break;
-        }
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let before = Snapshot::of(&sudoku.cell_grid);
+        let Puzzle { cell_grid, rows, columns, blocks, .. } = sudoku;
 
-        implicitly_solved::solve_single_candidate_cells(sudoku);
+        for (unit, group) in Unit::with_groups(rows, columns, blocks) {
+            let keys = eliminate_closed_groups_in(cell_grid, group);
+            if keys.is_empty() {
+                continue;
+            }
 
-        if i >= PUZZLE_TOTAL_CELL_COUNT * PUZZLE_TOTAL_CELL_COUNT {
-            println!("Gave up eliminating candidates from closed groups after {} iterations", i);
-            // Should never happen, but ensure we terminate
-            break;
+            if let Some(step) = Step::since(self.name(), &before, cell_grid) {
+                let closed_cells: Vector<_> = group.cells.iterate().copied().filter(|&coordinate| {
+                    let cell = &cell_grid[coordinate];
+                    cell.value.is_none() && keys.iterate().any(|&key| cell.potentially_valid_values.is_subset_of(key))
+                }).collect();
+                return StepResult::Progressed(step.with_units([unit]).with_cells(closed_cells));
+            }
         }
+
+        return StepResult::NoProgress;
     }
 }
 
-fn try_eliminate_closed_candidate_groups(sudoku: &mut Puzzle) -> bool {
+// Gives the candidates of each closed group that eliminated something
+fn eliminate_closed_groups_in(cell_grid: &mut CellGrid, cell_group: &CellGroup) -> Vector<CandidateSet> {
 
-    return 
-    try_eliminate_options_from_groups(&mut sudoku.rows)
-    || try_eliminate_options_from_groups(&mut sudoku.columns)
-    || try_eliminate_options_from_groups(&mut sudoku.blocks);
-}
+    let mut closed_keys = Vector::new();
 
-fn try_eliminate_options_from_groups(cell_group_vector: &mut Vector<CellGroup>) -> bool {
+    let cells_to_calculate_over = cell_group.unsolved(cell_grid);
+    let all_candidates: Vector<CandidateSet> = cells_to_calculate_over.iterate().map(|&coordinate| cell_grid[coordinate].potentially_valid_values).collect();
 
-    let mut any_eliminated = false;
-    
-    for cell_group in cell_group_vector {
-        
-        let mut dictionary : HashMap<&Vec<u8>, usize>= HashMap::new();
-        
-        let cells_to_calculate_over: Vector<_> = cell_group.cells.iterate().filter(|cell| cell.borrow().value.is_none()).collect();
-
-        let mut all_keys: Vector<_> = cells_to_calculate_over.iterate().map(|cell| cell.borrow().potentially_valid_values.clone()).collect();
-        all_keys.dedup();
-        for key in &all_keys {
-            dictionary.insert(key, 0);
-        }
+    // Each cell's candidates is a key, counting the cells whose candidates all fall within it
+    let mut dictionary: HashMap<CandidateSet, usize> = HashMap::new();
+    for &key in &all_candidates {
+        dictionary.entry(key).or_insert_with(|| all_candidates.iterate().filter(|candidates| candidates.is_subset_of(key)).count());
+    }
 
-        for cell_reference in &cells_to_calculate_over {
+    for (key, count) in dictionary {
 
-           all_keys
-           .iterate()
-           .filter(|key| key.is_superset_of(&cell_reference.borrow().potentially_valid_values))
-           .for_each(|matched_key| {
-                let count = dictionary.get(matched_key).expect("We are iterating over the keys that seeded the HashMap");
-                dictionary.insert(&matched_key, count + 1);
-           });
+        if key.len() != count {
+            continue;
+        }
 
+        if cells_to_calculate_over.len() == count {
+            continue;
         }
 
-        for dictionary_entry in dictionary {
-           
-            if dictionary_entry.0.len() != dictionary_entry.1
-            {
-                continue;
-            }
+        let mut key_eliminated = false;
+
+        for &coordinate in cells_to_calculate_over.iterate() {
 
-            if cells_to_calculate_over.len() == dictionary_entry.1 {
+            // if cell is one of the cells in the group then we shouldn't alter its candidates
+            // Only if a cell is actually mutated in some way do we want to report that something happened
+            // and that it is work continuing another iteration
+            if cell_grid[coordinate].potentially_valid_values.is_subset_of(key) {
                 continue;
             }
 
-            println!("cycle identified for key [{:?}]", &dictionary_entry.0);
- 
-            for cell in cells_to_calculate_over.iterate() {
-
-                // if cell is one of the cells in the group then we shouldn't alter its candidates
-                // Only if a cell is actually mutated in some way do we want to report that something happened
-                // and that it is work continuing another iteration
-                if cell.borrow().potentially_valid_values.is_subset_of(&dictionary_entry.0){
-                    continue;
-                }
+            key_eliminated = cell_grid[coordinate].discount_values(key) || key_eliminated;
+        }
 
-                any_eliminated = cell.borrow_mut().discount_values(dictionary_entry.0) || any_eliminated;
-            }
+        if key_eliminated {
+            closed_keys.push(key);
         }
     }
 
-    return any_eliminated;
+    return closed_keys;
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{cell::RefCell, rc::Rc};
-
-    use crate::sudoku::{core::{cell::Cell, cell_grid::CellReference, validatable_units::{CellGroup, PuzzleValidator}}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::implicitly_solved::solve_single_candidate_cells};
+    use crate::sudoku::{core::{cell_grid::Coordinate, validatable_units::PuzzleValidator}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{implicitly_solved::SingleCandidateCells, solver::Solver}};
 
     use super::*;
 
-    //todo: This is copied from validatable_units. Share this properly
-    fn cell_reference_from_value(cell_value_option: Option<u8>) -> CellReference {
-        
-        let new_cell_ref = Rc::new(RefCell::new(Cell::new()));
-        if let Some(value) = cell_value_option {
-            new_cell_ref.borrow_mut().set_value(value);
-        }
-        return new_cell_ref;
-    }
-
     #[test]
     fn eliminate_candidates_when_groups_of_cells_identified() {
-        let cell_a = cell_reference_from_value(None);
-        let cell_b = cell_reference_from_value(None);
-        let cell_c = cell_reference_from_value(None);
-        let cell_d = cell_reference_from_value(None);
-
-        cell_a.borrow_mut().discount_range(1..=7);
-        cell_b.borrow_mut().discount_range(1..=7);
-        cell_c.borrow_mut().discount_range(1..=6);
-        cell_d.borrow_mut().discount_value(1);
-        
-        let references = vec![
-            Rc::clone(&cell_a),
-            Rc::clone(&cell_b),
-            Rc::clone(&cell_c),
-            Rc::clone(&cell_d)
-        ];
+        let mut cell_grid = CellGrid::new();
+        let cell_a = Coordinate::new(0, 0);
+        let cell_b = Coordinate::new(0, 1);
+        let cell_c = Coordinate::new(0, 2);
+        let cell_d = Coordinate::new(0, 3);
+
+        cell_grid[cell_a].discount_range(1..=7);
+        cell_grid[cell_b].discount_range(1..=7);
+        cell_grid[cell_c].discount_range(1..=6);
+        cell_grid[cell_d].discount_value(1);
 
-        let group = CellGroup::new(references);
-        let some_eliminated = try_eliminate_options_from_groups(&mut vec![group]);
+        let group = CellGroup::new(vec![cell_a, cell_b, cell_c, cell_d]);
+        let some_eliminated = !eliminate_closed_groups_in(&mut cell_grid, &group).is_empty();
 
         assert!(some_eliminated);
-        assert_eq!(Rc::clone(&cell_c).borrow().potentially_valid_values, vec![7]);
-        assert_eq!(Rc::clone(&cell_d).borrow().potentially_valid_values, vec![2,3,4,5,6])
+        assert_eq!(cell_grid[cell_c].potentially_valid_values, CandidateSet::single(7));
+        assert_eq!(cell_grid[cell_d].potentially_valid_values, CandidateSet::from([2, 3, 4, 5, 6]))
     }
     
     #[test]
@@ -148,11 +118,11 @@ mod tests {
         let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
         
         let initial_cell_count = sudoku.count_cells_with_value();
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
 
-        solve_conjugate_groups(&mut sudoku);
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(ClosedCandidateGroups)]).solve(&mut sudoku);
         
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
         println!("before: {}, after: {}", initial_cell_count, sudoku.count_cells_with_value());
 
         assert!(sudoku.is_complete())
@@ -164,11 +134,11 @@ mod tests {
         let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
         
         let initial_cell_count = sudoku.count_cells_with_value();
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
 
-        solve_conjugate_groups(&mut sudoku);
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(ClosedCandidateGroups)]).solve(&mut sudoku);
         
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
         println!("before: {}, after: {}", initial_cell_count, sudoku.count_cells_with_value());
 
         assert!(sudoku.is_complete())
@@ -180,11 +150,11 @@ mod tests {
         let mut puzzle = Serializer::new().new_puzzle(test_case).expect("test data is valid");
         
         let initial_cell_count = puzzle.count_cells_with_value();
-        draw_all_rows(&puzzle.rows);
+        draw_all_rows(&puzzle);
 
-        solve_conjugate_groups(&mut puzzle);
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(ClosedCandidateGroups)]).solve(&mut puzzle);
         
-        draw_all_rows(&puzzle.rows);
+        draw_all_rows(&puzzle);
         println!("before: {}, after: {}", initial_cell_count, puzzle.count_cells_with_value());
         assert!(puzzle.count_cells_with_value() > initial_cell_count);
     }
@@ -0,0 +1,421 @@
+use crate::pretty::{aliases::*, iterable::*};
+
+use crate::sudoku::core::{cell_grid::CellGrid, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::{Step, Unit}};
+
+pub const SMALLEST_FISH: usize = 2;
+pub const LARGEST_FISH: usize = 4;
+
+// Where a fish was found, as indexes into the base and cover lines it was looked for in
+struct FoundFish {
+    value: u8,
+    base_indexes: Vector<usize>,
+    cover_indexes: Vector<usize>,
+    is_sashimi: bool,
+}
+
+pub struct Fish;
+
+impl Technique for Fish {
+    fn name(&self) -> StringSlice<'static> {
+        return "Fish";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 36;
+    }
+
+    // Smaller fish are tried first, as they are both cheaper to find and easier for a person to follow.
+    // Every basic fish comes before any finned one.
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        for finned in [false, true] {
+        for size in SMALLEST_FISH..=LARGEST_FISH {
+        for rows_are_base in [true, false] {
+
+            let mut found = Vector::new();
+            let step = Step::observe(self.name(), sudoku, |sudoku| {
+                let (base_lines, cover_lines) = if rows_are_base { (&sudoku.rows, &sudoku.columns) } else { (&sudoku.columns, &sudoku.rows) };
+                found = if finned {
+                    eliminate_finned_fish_of_size(&mut sudoku.cell_grid, base_lines, cover_lines, size)
+                } else {
+                    eliminate_fish_of_size(&mut sudoku.cell_grid, base_lines, cover_lines, size)
+                };
+                return !found.is_empty();
+            });
+
+            let Some(mut step) = step else {
+                continue;
+            };
+
+            // A sashimi fish is a kind of finned fish, so a mix of the two is still finned
+            step.technique = match (finned, found.iterate().all(|fish| fish.is_sashimi)) {
+                (false, _) => fish_name(size).to_string(),
+                (true, false) => format!("Finned {}", fish_name(size)),
+                (true, true) => format!("Sashimi {}", fish_name(size)),
+            };
+
+            let base_unit = |index: usize| if rows_are_base { Unit::Row(index) } else { Unit::Column(index) };
+            let cover_unit = |index: usize| if rows_are_base { Unit::Column(index) } else { Unit::Row(index) };
+
+            for fish in found {
+                let fish_cells: Vector<_> = fish.base_indexes
+                    .iterate()
+                    .flat_map(|&base_index| base_unit(base_index).cells(sudoku))
+                    .filter(|&coordinate| sudoku.cell_grid[coordinate].potentially_valid_values.contains(fish.value))
+                    .collect();
+
+                step = step
+                    .with_units(fish.base_indexes.iterate().map(|&index| base_unit(index)).chain(fish.cover_indexes.iterate().map(|&index| cover_unit(index))))
+                    .with_cells(fish_cells);
+            }
+
+            return StepResult::Progressed(step);
+        }}}
+
+        return StepResult::NoProgress;
+    }
+}
+
+pub fn fish_name(size: usize) -> StringSlice<'static> {
+    return match size {
+        2 => "X-Wing",
+        3 => "Swordfish",
+        4 => "Jellyfish",
+        _ => "Fish",
+    };
+}
+
+// When a value's candidates in N base lines all sit within the same N cover lines, each cover line
+// must take its value from one of the base lines, so the value can be removed from the rest of the cover lines.
+// Base lines are rows and cover lines columns, or the other way round; the position of a cell along a base line
+// is then the index of the cover line it is in, and vice versa.
+fn eliminate_fish_of_size(cell_grid: &mut CellGrid, base_lines: &[CellGroup], cover_lines: &[CellGroup], size: usize) -> Vector<FoundFish> {
+
+    let mut found = Vector::new();
+
+    for value in cell_grid.shape.values() {
+
+        let candidate_base_lines: Vector<(usize, Vector<usize>)> = base_lines
+            .iterate()
+            .enumerate()
+            .map(|(base_index, line)| (base_index, line.positions_with_candidate(cell_grid, value)))
+            .filter(|(_, positions)| !positions.is_empty() && positions.len() <= size)
+            .collect();
+
+        for fish in candidate_base_lines.combinations_of_size(size) {
+
+            let base_indexes: Vector<usize> = fish.iterate().map(|(base_index, _)| *base_index).collect();
+            let mut cover_indexes: Vector<usize> = fish.iterate().flat_map(|(_, positions)| positions.clone()).collect();
+            cover_indexes.sort();
+            cover_indexes.dedup();
+
+            if cover_indexes.len() != size {
+                continue;
+            }
+
+            let mut fish_eliminated = false;
+
+            for &cover_index in &cover_indexes {
+                for (position, &cell) in cover_lines[cover_index].cells.iterate().enumerate() {
+                    if base_indexes.contains(&position) {
+                        continue;
+                    }
+                    fish_eliminated = cell_grid[cell].discount_value(value) || fish_eliminated;
+                }
+            }
+
+            if fish_eliminated {
+                found.push(FoundFish { value, base_indexes, cover_indexes, is_sashimi: false });
+            }
+        }
+    }
+
+    return found;
+}
+
+// A finned fish is a fish with a few extra candidates, the fins, in its base lines, all sitting in one block.
+// Either a fin holds the value, or the fish does; a cell in a cover line that sees every fin loses the value either way.
+// When a base line has only one candidate left once the fins are ignored, the fish is called sashimi.
+// The fin block is picked first: every candidate outside it has to be in a cover line, which leaves only a few
+// cover lines to choose, from those crossing the block.
+fn eliminate_finned_fish_of_size(cell_grid: &mut CellGrid, base_lines: &[CellGroup], cover_lines: &[CellGroup], size: usize) -> Vector<FoundFish> {
+
+    let mut found = Vector::new();
+    let shape = cell_grid.shape;
+
+    // The fins in a base line all share a block, so there can be no more of them than a block is wide
+    let most_fins = shape.block_height.max(shape.block_width);
+
+    for value in shape.values() {
+
+        // Positions along each base line, as bitmasks, so the cover lines they need are the bits set
+        let candidate_base_lines: Vector<(usize, u32)> = base_lines
+            .iterate()
+            .enumerate()
+            .map(|(base_index, line)| (base_index, mask_of(&line.positions_with_candidate(cell_grid, value))))
+            .filter(|&(_, positions)| positions != 0 && positions.count_ones() as usize <= size + most_fins)
+            .collect();
+
+        for fin_block in 0..shape.dimension() {
+
+            let split_lines: Vector<SplitLine> = candidate_base_lines
+                .iterate()
+                .map(|&(base_index, positions)| {
+                    let in_block = base_lines[base_index].cells.iterate().enumerate()
+                        .filter(|&(_, &cell)| shape.block_of(cell) == fin_block)
+                        .fold(0, |mask, (position, _)| mask | 1 << position);
+                    SplitLine { base_index, outside: positions & !in_block, inside: positions & in_block }
+                })
+                .filter(|line| line.outside.count_ones() as usize <= size)
+                .collect();
+
+            let mut fish = Vector::new();
+            choose_base_lines(&split_lines, size, &mut Vector::new(), 0, &mut fish);
+
+            for chosen in fish {
+
+                let base_indexes: Vector<usize> = chosen.iterate().map(|line| line.base_index).collect();
+                let needed = chosen.iterate().fold(0, |mask, line| mask | line.outside);
+                let spare = chosen.iterate().fold(0, |mask, line| mask | line.inside) & !needed;
+                let to_add = size - needed.count_ones() as usize;
+
+                // Without anything left over for fins this would be a basic fish
+                if (spare.count_ones() as usize) <= to_add {
+                    continue;
+                }
+
+                for extra in positions_in(spare).combinations_of_size(to_add) {
+
+                    let covers = needed | mask_of(&extra);
+                    if !chosen.iterate().all(|line| (line.outside | line.inside) & covers != 0) {
+                        continue;
+                    }
+
+                    let cover_indexes = positions_in(covers);
+                    let mut fish_eliminated = false;
+
+                    // A cell left in a cover line is off the base lines, and a fin is off the cover lines,
+                    // so the only way the cell can see every fin is by sharing their block
+                    for &cover_index in &cover_indexes {
+                        for (position, &cell) in cover_lines[cover_index].cells.iterate().enumerate() {
+                            if base_indexes.contains(&position) || shape.block_of(cell) != fin_block {
+                                continue;
+                            }
+                            fish_eliminated = cell_grid[cell].discount_value(value) || fish_eliminated;
+                        }
+                    }
+
+                    if fish_eliminated {
+                        let is_sashimi = chosen.iterate().any(|line| ((line.outside | line.inside) & covers).count_ones() == 1);
+                        found.push(FoundFish { value, base_indexes: base_indexes.clone(), cover_indexes, is_sashimi });
+                    }
+                }
+            }
+        }
+    }
+
+    return found;
+}
+
+// A base line's candidates split by whether they are in the fin block
+#[derive(Clone, Copy)]
+struct SplitLine {
+    base_index: usize,
+    outside: u32,
+    inside: u32,
+}
+
+// Adds base lines one at a time, dropping any choice whose candidates outside the fin block already need more cover
+// lines than the fish has. At least one of the lines has to reach into the fin block, or there would be no fins.
+fn choose_base_lines(lines: &[SplitLine], size: usize, chosen: &mut Vector<SplitLine>, needed: u32, found: &mut Vector<Vector<SplitLine>>) {
+
+    if chosen.len() == size {
+        if chosen.iterate().any(|line| line.inside != 0) {
+            found.push(chosen.clone());
+        }
+        return;
+    }
+
+    for (index, &line) in lines.iterate().enumerate() {
+
+        let needed = needed | line.outside;
+        if needed.count_ones() as usize > size {
+            continue;
+        }
+
+        chosen.push(line);
+        choose_base_lines(&lines[index + 1..], size, chosen, needed, found);
+        chosen.pop();
+    }
+}
+
+fn mask_of(positions: &[usize]) -> u32 {
+    return positions.iterate().fold(0, |mask, &position| mask | 1 << position);
+}
+
+fn positions_in(mask: u32) -> Vector<usize> {
+    return (0..u32::BITS as usize).filter(|&position| mask & 1 << position != 0).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::sudoku::{core::{consts::PUZZLE_DIMENTION, dancing_links::find_exact_cover_solutions, puzzle::SeedGrid, shape::Shape, validatable_units::PuzzleValidator}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    fn keep_value_only_in_columns(sudoku: &mut Puzzle, row: usize, value: u8, columns: &[usize]) {
+        for column in 0..PUZZLE_DIMENTION {
+            if !columns.contains(&column) {
+                sudoku.cell_grid[row][column].discount_value(value);
+            }
+        }
+    }
+
+    fn has_candidate(sudoku: &Puzzle, row: usize, column: usize, value: u8) -> bool {
+        return sudoku.cell_grid[row][column].potentially_valid_values.contains(value);
+    }
+
+    #[test]
+    fn x_wing_removes_value_from_rest_of_both_columns() {
+        let mut sudoku = Puzzle::default();
+        keep_value_only_in_columns(&mut sudoku, 1, 5, &[2, 7]);
+        keep_value_only_in_columns(&mut sudoku, 4, 5, &[2, 7]);
+
+        let result = Fish.apply(&mut sudoku);
+
+        assert!(matches!(&result, StepResult::Progressed(step) if step.technique == "X-Wing"));
+        for row in 0..PUZZLE_DIMENTION {
+            let is_fish_row = row == 1 || row == 4;
+            assert_eq!(has_candidate(&sudoku, row, 2, 5), is_fish_row);
+            assert_eq!(has_candidate(&sudoku, row, 7, 5), is_fish_row);
+            assert_eq!(has_candidate(&sudoku, row, 3, 5), !is_fish_row);
+        }
+    }
+
+    #[test]
+    fn swordfish_allows_base_lines_with_only_some_of_the_cover_lines() {
+        let mut sudoku = Puzzle::default();
+        keep_value_only_in_columns(&mut sudoku, 0, 3, &[1, 4]);
+        keep_value_only_in_columns(&mut sudoku, 4, 3, &[4, 7]);
+        keep_value_only_in_columns(&mut sudoku, 8, 3, &[1, 7]);
+
+        let result = Fish.apply(&mut sudoku);
+
+        assert!(matches!(&result, StepResult::Progressed(step) if step.technique == "Swordfish"));
+        assert!(!has_candidate(&sudoku, 2, 1, 3));
+        assert!(!has_candidate(&sudoku, 6, 4, 3));
+        assert!(!has_candidate(&sudoku, 5, 7, 3));
+        assert!(has_candidate(&sudoku, 0, 1, 3));
+        assert!(has_candidate(&sudoku, 4, 7, 3));
+    }
+
+    #[test]
+    fn fish_with_columns_as_base_removes_value_from_rows() {
+        let mut sudoku = Puzzle::default();
+        for column in [0, 5] {
+            for row in 0..PUZZLE_DIMENTION {
+                if row != 3 && row != 6 {
+                    sudoku.cell_grid[row][column].discount_value(9);
+                }
+            }
+        }
+
+        let some_eliminated = Fish.apply(&mut sudoku) != StepResult::NoProgress;
+
+        assert!(some_eliminated);
+        assert!(!has_candidate(&sudoku, 3, 8, 9));
+        assert!(!has_candidate(&sudoku, 6, 1, 9));
+        assert!(has_candidate(&sudoku, 3, 5, 9));
+    }
+
+    #[test]
+    fn no_fish_when_base_lines_spread_over_too_many_cover_lines() {
+        let mut sudoku = Puzzle::default();
+        keep_value_only_in_columns(&mut sudoku, 1, 5, &[2, 7]);
+        keep_value_only_in_columns(&mut sudoku, 4, 5, &[2, 8]);
+
+        // Column 8 would be a fin, so only a basic fish is looked for
+        let some_eliminated = !eliminate_fish_of_size(&mut sudoku.cell_grid, &sudoku.rows, &sudoku.columns, 2).is_empty();
+
+        assert!(!some_eliminated);
+    }
+
+    #[test]
+    fn finned_x_wing_removes_value_from_cover_line_cells_that_see_the_fin() {
+        let mut sudoku = Puzzle::default();
+        keep_value_only_in_columns(&mut sudoku, 1, 5, &[2, 7]);
+        keep_value_only_in_columns(&mut sudoku, 4, 5, &[2, 7, 8]);
+
+        let result = Fish.apply(&mut sudoku);
+
+        assert!(matches!(&result, StepResult::Progressed(step) if step.technique == "Finned X-Wing"));
+        assert!(!has_candidate(&sudoku, 3, 7, 5));
+        assert!(!has_candidate(&sudoku, 5, 7, 5));
+        assert!(has_candidate(&sudoku, 0, 7, 5));
+        assert!(has_candidate(&sudoku, 3, 2, 5));
+        assert!(has_candidate(&sudoku, 4, 8, 5));
+    }
+
+    #[test]
+    fn sashimi_x_wing_removes_value_from_cover_line_cells_that_see_the_fin() {
+        let mut sudoku = Puzzle::default();
+        keep_value_only_in_columns(&mut sudoku, 1, 5, &[2, 7]);
+        keep_value_only_in_columns(&mut sudoku, 4, 5, &[7, 8]);
+
+        let result = Fish.apply(&mut sudoku);
+
+        assert!(matches!(&result, StepResult::Progressed(step) if step.technique == "Sashimi X-Wing"));
+        assert!(!has_candidate(&sudoku, 3, 7, 5));
+        assert!(!has_candidate(&sudoku, 5, 7, 5));
+        assert!(has_candidate(&sudoku, 8, 7, 5));
+        assert!(has_candidate(&sudoku, 5, 2, 5));
+    }
+
+    #[test]
+    fn no_finned_fish_when_fins_in_different_blocks() {
+        let mut sudoku = Puzzle::default();
+        keep_value_only_in_columns(&mut sudoku, 1, 5, &[2, 7, 0]);
+        keep_value_only_in_columns(&mut sudoku, 4, 5, &[2, 7, 8]);
+
+        let some_eliminated = Fish.apply(&mut sudoku) != StepResult::NoProgress;
+
+        assert!(!some_eliminated);
+    }
+
+    #[test]
+    fn finishes_quickly_on_a_16x16_puzzle() {
+        // A solved grid with two cells in five kept as givens
+        let shape = Shape::new(4, 4).expect("test shape is valid");
+        let solution = find_exact_cover_solutions(shape, &vec![vec![None; 16]; 16], 1).remove(0);
+        let seed: SeedGrid = (0..16).map(|row| (0..16).map(|column| solution[row][column].filter(|_| (row * 7 + column * 13) % 5 < 2)).collect()).collect();
+        let mut sudoku = Puzzle::from_seed(shape, &seed);
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
+
+        let started = Instant::now();
+        let mut steps = 0;
+        while Fish.apply(&mut sudoku) != StepResult::NoProgress {
+            steps += 1;
+        }
+
+        assert!(steps > 0);
+        assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+    }
+
+    #[test]
+    fn solves_puzzle_that_single_candidate_cells_cannot() {
+        let test_case = "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5";
+
+        let mut only_single_candidates = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut only_single_candidates);
+        assert!(!only_single_candidates.is_complete());
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(Fish)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        assert!(sudoku.is_complete());
+    }
+}
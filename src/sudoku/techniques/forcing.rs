@@ -0,0 +1,338 @@
+use crate::pretty::aliases::*;
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::Coordinate, puzzle::Puzzle, validatable_units::PuzzleValidator};
+
+use super::{
+    conjugate_groups::ClosedCandidateGroups,
+    hidden_singles::HiddenSingles,
+    hidden_subsets::HiddenSubsets,
+    implicitly_solved::SingleCandidateCells,
+    solver::{Solver, StepResult, Technique},
+    step::Step
+};
+
+// Enough rounds of singles and subsets to follow most short chains, without the search turning into brute force
+pub const DEFAULT_FORCING_DEPTH: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForcingKind {
+    // Assuming a single candidate leads to a contradiction, so it can't be true
+    Nishio,
+    // Every candidate of one cell leads to the same conclusion
+    CellForcingChain,
+    // Every place a value could go in one unit leads to the same conclusion
+    UnitForcingChain,
+}
+
+#[derive(Debug)]
+pub struct Forcing {
+    pub kind: ForcingKind,
+    // One of these is true. Each was tried in turn on a copy of the puzzle.
+    pub assumptions: Vector<(Coordinate, u8)>,
+    pub eliminations: Vector<(Coordinate, u8)>,
+    pub placements: Vector<(Coordinate, u8)>,
+}
+
+pub struct ForcingChains {
+    pub depth_limit: usize,
+}
+
+impl Technique for ForcingChains {
+    fn name(&self) -> StringSlice<'static> {
+        return "Forcing chains";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 80;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let Some(forcing) = find_forcing(sudoku, self.depth_limit) else {
+            return StepResult::NoProgress;
+        };
+
+        let assumed_cells: Vector<_> = forcing.assumptions.iterate().map(|&(coordinate, _)| coordinate).collect();
+        let step = Step::observe(self.name(), sudoku, |sudoku| apply_forcing(sudoku, &forcing));
+        return StepResult::from(step.map(|step| step.with_cells(assumed_cells)));
+    }
+}
+
+fn apply_forcing(sudoku: &mut Puzzle, forcing: &Forcing) -> bool {
+
+    for &(coordinate, value) in &forcing.eliminations {
+        sudoku.cell_grid[coordinate].discount_value(value);
+    }
+
+    for &(coordinate, value) in &forcing.placements {
+        sudoku.cell_grid[coordinate].set_value(value);
+    }
+
+    return true;
+}
+
+// Nishio is the easiest of the three to explain, so it's tried first. Only the first forcing found is returned,
+// as every trial is expensive and the puzzle changes as soon as it's applied.
+pub fn find_forcing(sudoku: &Puzzle, depth_limit: usize) -> Option<Forcing> {
+
+    return find_nishio(sudoku, depth_limit)
+        .or_else(|| find_cell_forcing_chain(sudoku, depth_limit))
+        .or_else(|| find_unit_forcing_chain(sudoku, depth_limit));
+}
+
+fn find_nishio(sudoku: &Puzzle, depth_limit: usize) -> Option<Forcing> {
+
+    for coordinate in sudoku.cell_grid.coordinates() {
+        for value in candidates_at(sudoku, coordinate) {
+            if assume(sudoku, coordinate, value, depth_limit).is_none() {
+                return Some(Forcing {
+                    kind: ForcingKind::Nishio,
+                    assumptions: vec![(coordinate, value)],
+                    eliminations: vec![(coordinate, value)],
+                    placements: vec![]
+                });
+            }
+        }
+    }
+
+    return None;
+}
+
+fn find_cell_forcing_chain(sudoku: &Puzzle, depth_limit: usize) -> Option<Forcing> {
+
+    for coordinate in sudoku.cell_grid.coordinates() {
+
+        let assumptions: Vector<(Coordinate, u8)> = candidates_at(sudoku, coordinate).into_iter().map(|value| (coordinate, value)).collect();
+        if assumptions.len() < 2 {
+            continue;
+        }
+
+        if let Some(forcing) = forcing_from(sudoku, ForcingKind::CellForcingChain, assumptions, depth_limit) {
+            return Some(forcing);
+        }
+    }
+
+    return None;
+}
+
+fn find_unit_forcing_chain(sudoku: &Puzzle, depth_limit: usize) -> Option<Forcing> {
+
+    for group in sudoku.rows.iterate().chain(sudoku.columns.iterate()).chain(sudoku.blocks.iterate()) {
+        for value in sudoku.shape().values() {
+
+            let assumptions: Vector<(Coordinate, u8)> = group
+                .positions_with_candidate(&sudoku.cell_grid, value)
+                .into_iter()
+                .map(|position| (group.cells[position], value))
+                .collect();
+
+            if assumptions.len() < 2 {
+                continue;
+            }
+
+            if let Some(forcing) = forcing_from(sudoku, ForcingKind::UnitForcingChain, assumptions, depth_limit) {
+                return Some(forcing);
+            }
+        }
+    }
+
+    return None;
+}
+
+// One of the assumptions has to be true, so anything that follows from every one that doesn't contradict itself is true too
+fn forcing_from(sudoku: &Puzzle, kind: ForcingKind, assumptions: Vector<(Coordinate, u8)>, depth_limit: usize) -> Option<Forcing> {
+
+    let outcomes: Vector<Puzzle> = assumptions
+        .iterate()
+        .filter_map(|&(coordinate, value)| assume(sudoku, coordinate, value, depth_limit))
+        .collect();
+
+    // Nothing works out, so the puzzle was already broken and there's nothing sensible to conclude
+    if outcomes.is_empty() {
+        return None;
+    }
+
+    let mut eliminations = Vector::new();
+    let mut placements = Vector::new();
+
+    for coordinate in sudoku.cell_grid.coordinates() {
+
+        if sudoku.cell_grid[coordinate].value.is_some() {
+            continue;
+        }
+
+        if let Some(value) = outcomes[0].cell_grid[coordinate].value {
+            if outcomes.iterate().all(|outcome| outcome.cell_grid[coordinate].value == Some(value)) {
+                placements.push((coordinate, value));
+                continue;
+            }
+        }
+
+        for value in candidates_at(sudoku, coordinate) {
+            if !outcomes.iterate().any(|outcome| is_possible(outcome, coordinate, value)) {
+                eliminations.push((coordinate, value));
+            }
+        }
+    }
+
+    if eliminations.is_empty() && placements.is_empty() {
+        return None;
+    }
+
+    return Some(Forcing {
+        kind,
+        assumptions,
+        eliminations,
+        placements
+    });
+}
+
+// Place the value on a copy of the puzzle and follow the consequences for at most depth_limit rounds.
+// Gives back the copy, or nothing when the assumption led to a contradiction.
+fn assume(sudoku: &Puzzle, coordinate: Coordinate, value: u8, depth_limit: usize) -> Option<Puzzle> {
+
+    let mut copy = sudoku.clone();
+    copy.cell_grid[coordinate].set_value(value);
+
+    let singles = Solver::new(vec![Box::new(SingleCandidateCells)]);
+    let follow_ups: [&dyn Technique; 3] = [&HiddenSingles, &ClosedCandidateGroups, &HiddenSubsets];
+
+    for _ in 0..depth_limit {
+
+        singles.solve(&mut copy);
+        if has_contradiction(&copy) {
+            return None;
+        }
+
+        let any_changed = follow_ups.iterate().any(|technique| technique.apply(&mut copy) != StepResult::NoProgress);
+
+        if !any_changed {
+            break;
+        }
+    }
+
+    singles.solve(&mut copy);
+    if has_contradiction(&copy) {
+        return None;
+    }
+
+    return Some(copy);
+}
+
+// Two cells in a unit with the same value, a cell with nowhere left to go, or a value with nowhere left to go in a unit
+fn has_contradiction(sudoku: &Puzzle) -> bool {
+
+    if !sudoku.is_valid() {
+        return true;
+    }
+
+    let any_stuck_cell = sudoku.cell_grid.cells.iterate().any(|cell| cell.value.is_none() && cell.potentially_valid_values.is_empty());
+
+    let any_stuck_value = sudoku.rows.iterate().chain(sudoku.columns.iterate()).chain(sudoku.blocks.iterate()).any(|group| {
+        sudoku.shape().values().any(|value| group.cells_in(&sudoku.cell_grid).all(|cell| {
+            cell.value != Some(value) && !cell.potentially_valid_values.contains(value)
+        }))
+    });
+
+    return any_stuck_cell || any_stuck_value;
+}
+
+fn is_possible(sudoku: &Puzzle, coordinate: Coordinate, value: u8) -> bool {
+    let cell = &sudoku.cell_grid[coordinate];
+    return cell.value == Some(value) || (cell.value.is_none() && cell.potentially_valid_values.contains(value));
+}
+
+fn candidates_at(sudoku: &Puzzle, coordinate: Coordinate) -> CandidateSet {
+    return sudoku.cell_grid[coordinate].potentially_valid_values;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::consts::PUZZLE_MAXIMUM_VALUE, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::backtracking_brute_force::find_solutions};
+
+    use super::*;
+
+    fn keep_only_candidates(sudoku: &mut Puzzle, coordinate: Coordinate, values: &[u8]) {
+        let discounted: Vector<u8> = (1..=PUZZLE_MAXIMUM_VALUE).filter(|value| !values.contains(value)).collect();
+        sudoku.cell_grid[coordinate].discount_values(discounted);
+    }
+
+    #[test]
+    fn nishio_removes_candidate_that_empties_another_cell() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 5), &[1, 2]);
+
+        let forcing = find_forcing(&sudoku, DEFAULT_FORCING_DEPTH).expect("a third cell can't take 1 or 2");
+
+        assert_eq!(forcing.kind, ForcingKind::Nishio);
+        assert_eq!(forcing.assumptions.len(), 1);
+        let (coordinate, value) = forcing.eliminations[0];
+        assert_eq!(coordinate.row, 0);
+        assert!(value == 1 || value == 2);
+    }
+
+    #[test]
+    fn cell_forcing_chain_keeps_what_every_candidate_agrees_on() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 4), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(4, 0), &[2, 3]);
+
+        let forcing = find_cell_forcing_chain(&sudoku, DEFAULT_FORCING_DEPTH).expect("both candidates of the first cell put a 3 near (4, 4)");
+
+        assert_eq!(forcing.assumptions, vec![(Coordinate::new(0, 0), 1), (Coordinate::new(0, 0), 2)]);
+        assert!(forcing.eliminations.contains(&(Coordinate::new(4, 4), 3)));
+    }
+
+    #[test]
+    fn unit_forcing_chain_keeps_what_every_position_agrees_on() {
+        let mut sudoku = Puzzle::default();
+        // 5 in row 0 is at (0, 0) or (0, 4), and either way (1, 2) or (1, 6) loses its only other option
+        for column in [1, 2, 3, 5, 6, 7, 8] {
+            sudoku.cell_grid[0][column].discount_value(5);
+        }
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 2), &[5, 7]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 4), &[5, 7]);
+
+        let forcing = find_unit_forcing_chain(&sudoku, DEFAULT_FORCING_DEPTH).expect("both places for 5 agree on row 1");
+
+        assert!(forcing.eliminations.iterate().any(|&(coordinate, value)| coordinate.row == 1 && value == 7));
+    }
+
+    #[test]
+    fn depth_limit_bounds_how_far_consequences_are_followed() {
+        let mut sudoku = Puzzle::default();
+        // (0, 8) is the only place for 2 in its row once (0, 0) is taken, and the only place for 3 in its column.
+        // Only following the hidden single shows that both can't be true.
+        for column in 1..8 {
+            sudoku.cell_grid[0][column].discount_value(2);
+        }
+        for row in 1..9 {
+            sudoku.cell_grid[row][8].discount_value(3);
+        }
+
+        assert!(assume(&sudoku, Coordinate::new(0, 0), 1, 0).is_some());
+        assert!(assume(&sudoku, Coordinate::new(0, 0), 1, 1).is_none());
+    }
+
+    #[test]
+    fn never_contradicts_the_solution() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let solution = find_solutions(&mut solved, 1).remove(0);
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
+        for _ in 0..3 {
+            ForcingChains { depth_limit: 2 }.apply(&mut sudoku);
+        }
+        draw_all_rows(&sudoku);
+
+        for coordinate in sudoku.cell_grid.coordinates() {
+            let cell = &sudoku.cell_grid[coordinate];
+            let expected = solution[coordinate.row][coordinate.column].expect("solution is complete");
+            assert!(cell.value == Some(expected) || cell.potentially_valid_values.contains(expected));
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::pretty::aliases::*;
+use crate::sudoku::core::{cell_grid::{CellGrid, Coordinate}, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::{Snapshot, Step, Unit}};
+
+pub struct HiddenSingles;
+
+impl Technique for HiddenSingles {
+    fn name(&self) -> StringSlice<'static> {
+        return "Hidden singles";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 15;
+    }
+
+    // One unit at a time, so each step says where the value had nowhere else to go
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let before = Snapshot::of(&sudoku.cell_grid);
+        let Puzzle { cell_grid, rows, columns, blocks, .. } = sudoku;
+
+        for (unit, group) in Unit::with_groups(rows, columns, blocks) {
+            if !place_hidden_singles_in(cell_grid, group) {
+                continue;
+            }
+
+            if let Some(step) = Step::since(self.name(), &before, cell_grid) {
+                let placed_cells: Vector<_> = step.placed.iterate().map(|&(coordinate, _)| coordinate).collect();
+                return StepResult::Progressed(step.with_units([unit]).with_cells(placed_cells));
+            }
+        }
+
+        return StepResult::NoProgress;
+    }
+}
+
+fn place_hidden_singles_in(cell_grid: &mut CellGrid, cell_group: &CellGroup) -> bool {
+
+    let mut any_placed = false;
+
+    for value in cell_grid.shape.values() {
+
+        // Candidates are only tidied up between passes, so a value placed earlier in this pass could still be a candidate elsewhere
+        if cell_group.cells_in(cell_grid).any(|cell| cell.value == Some(value)) {
+            continue;
+        }
+
+        let cells_with_candidate: Vector<Coordinate> = cell_group
+            .cells
+            .iterate()
+            .copied()
+            .filter(|&coordinate| cell_grid[coordinate].potentially_valid_values.contains(value))
+            .collect();
+
+        if cells_with_candidate.len() != 1 {
+            continue;
+        }
+
+        cell_grid[cells_with_candidate[0]].set_value(value);
+        any_placed = true;
+    }
+
+    return any_placed;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::validatable_units::PuzzleValidator, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    #[test]
+    fn place_value_that_fits_in_only_one_cell_of_group() {
+        let mut cell_grid = CellGrid::new();
+        let (cell_a, cell_b, cell_c) = (Coordinate::new(0, 0), Coordinate::new(0, 1), Coordinate::new(0, 2));
+
+        cell_grid[cell_a].discount_value(1);
+        cell_grid[cell_b].discount_value(1);
+
+        let group = CellGroup::new(vec![cell_a, cell_b, cell_c]);
+        let any_placed = place_hidden_singles_in(&mut cell_grid, &group);
+
+        assert!(any_placed);
+        assert_eq!(cell_grid[cell_c].value, Some(1));
+        assert!(cell_grid[cell_a].value.is_none());
+        assert!(cell_grid[cell_b].value.is_none());
+    }
+
+    #[test]
+    fn place_nothing_when_value_already_in_group() {
+        let mut cell_grid = CellGrid::new();
+        let (cell_a, cell_b, cell_c) = (Coordinate::new(0, 0), Coordinate::new(0, 1), Coordinate::new(0, 2));
+
+        cell_grid[cell_a].set_value(1);
+        cell_grid[cell_c].discount_value(1);
+
+        let group = CellGroup::new(vec![cell_a, cell_b, cell_c]);
+        place_hidden_singles_in(&mut cell_grid, &group);
+
+        assert!(cell_grid[cell_b].value.is_none());
+    }
+
+    #[test]
+    fn solves_puzzle_that_single_candidate_cells_cannot() {
+        let test_case = "2...8.3...6..7..84.3.5..2.9...1.54.8.........4.27.6...3.1..7.4.72..4..6...4.1...3";
+
+        let mut only_single_candidates = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut only_single_candidates);
+        assert!(!only_single_candidates.is_complete());
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(HiddenSingles)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        assert!(sudoku.is_complete());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::pretty::aliases::*;
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::CellGrid, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::{Snapshot, Step, Unit}};
+
+pub struct HiddenSubsets;
+
+impl Technique for HiddenSubsets {
+    fn name(&self) -> StringSlice<'static> {
+        return "Hidden subsets";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 34;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let before = Snapshot::of(&sudoku.cell_grid);
+        let Puzzle { cell_grid, rows, columns, blocks, .. } = sudoku;
+
+        for (unit, group) in Unit::with_groups(rows, columns, blocks) {
+            let subsets = eliminate_hidden_subsets_in(cell_grid, group);
+            if subsets.is_empty() {
+                continue;
+            }
+
+            if let Some(step) = Step::since(self.name(), &before, cell_grid) {
+                let subset_cells: Vector<_> = group.cells.iterate().copied().filter(|&coordinate| {
+                    let cell = &cell_grid[coordinate];
+                    subsets.iterate().any(|&subset| !(cell.potentially_valid_values & subset).is_empty())
+                }).collect();
+                return StepResult::Progressed(step.with_units([unit]).with_cells(subset_cells));
+            }
+        }
+
+        return StepResult::NoProgress;
+    }
+}
+
+// A hidden subset is N values that, between them, only fit in N cells of the group.
+// Those cells must hold exactly those values, so any other candidate in them can go.
+// Gives each subset that eliminated something.
+fn eliminate_hidden_subsets_in(cell_grid: &mut CellGrid, cell_group: &CellGroup) -> Vector<CandidateSet> {
+
+    let mut eliminating_subsets = Vector::new();
+
+    let cells_to_calculate_over = cell_group.unsolved(cell_grid);
+
+    // Past half the empty cells, the values left over are a naked subset in the other cells, which closed candidate
+    // groups already finds. A subset as large as the number of empty cells is the whole group, and tells us nothing.
+    let largest_size = cells_to_calculate_over.len() / 2;
+
+    for subset_size in 2..=largest_size {
+
+        // Where each value fits, as a bitmask over the empty cells. A value that fits in more cells than the subset
+        // holds can never be part of it.
+        let positions: Vector<(u8, u32)> = cell_grid.shape.values()
+            .map(|value| (value, cells_to_calculate_over.iterate().enumerate()
+                .filter(|&(_, &coordinate)| cell_grid[coordinate].potentially_valid_values.contains(value))
+                .fold(0u32, |mask, (index, _)| mask | 1 << index)))
+            .filter(|&(_, mask)| mask != 0 && mask.count_ones() as usize <= subset_size)
+            .collect();
+
+        let mut subsets = Vector::new();
+        find_hidden_subsets(&positions, subset_size, CandidateSet::new(), 0, &mut subsets);
+
+        for (subset, cells_holding_subset) in subsets {
+
+            let mut subset_eliminated = false;
+
+            for (index, &coordinate) in cells_to_calculate_over.iterate().enumerate() {
+                if cells_holding_subset & 1 << index == 0 {
+                    continue;
+                }
+                let other_values = cell_grid[coordinate].potentially_valid_values - subset;
+                subset_eliminated = cell_grid[coordinate].discount_values(other_values) || subset_eliminated;
+            }
+
+            if subset_eliminated {
+                eliminating_subsets.push(subset);
+            }
+        }
+    }
+
+    return eliminating_subsets;
+}
+
+// Adds values one at a time, dropping any set that already spreads over more cells than the subset holds,
+// so only the few sets that stay small are ever looked at
+fn find_hidden_subsets(positions: &[(u8, u32)], subset_size: usize, subset: CandidateSet, cells: u32, found: &mut Vector<(CandidateSet, u32)>) {
+
+    if subset.len() == subset_size {
+        if cells.count_ones() as usize == subset_size {
+            found.push((subset, cells));
+        }
+        return;
+    }
+
+    for (index, &(value, mask)) in positions.iterate().enumerate() {
+
+        let cells = cells | mask;
+        if cells.count_ones() as usize > subset_size {
+            continue;
+        }
+
+        let mut subset = subset;
+        subset.insert(value);
+        find_hidden_subsets(&positions[index + 1..], subset_size, subset, cells, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::{cell_grid::Coordinate, shape::Shape, validatable_units::PuzzleValidator}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{conjugate_groups::ClosedCandidateGroups, implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    // The first row of an empty grid
+    fn first_row() -> (CellGrid, Vector<Coordinate>) {
+        return (CellGrid::new(), (0..9).map(|column| Coordinate::new(0, column)).collect());
+    }
+
+    #[test]
+    fn eliminate_other_candidates_from_cells_holding_hidden_pair() {
+        let (mut cell_grid, cells) = first_row();
+
+        cells.iterate().skip(2).for_each(|&cell| {cell_grid[cell].discount_values([1, 2]);});
+
+        let group = CellGroup::new(cells.clone());
+        let some_eliminated = !eliminate_hidden_subsets_in(&mut cell_grid, &group).is_empty();
+
+        assert!(some_eliminated);
+        assert_eq!(cell_grid[cells[0]].potentially_valid_values, CandidateSet::from([1, 2]));
+        assert_eq!(cell_grid[cells[1]].potentially_valid_values, CandidateSet::from([1, 2]));
+        assert_eq!(cell_grid[cells[2]].potentially_valid_values, (3..=9).collect::<CandidateSet>());
+    }
+
+    #[test]
+    fn eliminate_other_candidates_from_cells_holding_hidden_triple() {
+        let (mut cell_grid, cells) = first_row();
+
+        // 1, 2 and 3 only fit in the first three cells, even though none of those cells holds all three
+        cell_grid[cells[0]].discount_value(3);
+        cell_grid[cells[1]].discount_value(1);
+        cell_grid[cells[2]].discount_value(2);
+        cells.iterate().skip(3).for_each(|&cell| {cell_grid[cell].discount_values([1, 2, 3]);});
+
+        let group = CellGroup::new(cells.clone());
+        let some_eliminated = !eliminate_hidden_subsets_in(&mut cell_grid, &group).is_empty();
+
+        assert!(some_eliminated);
+        assert_eq!(cell_grid[cells[0]].potentially_valid_values, CandidateSet::from([1, 2]));
+        assert_eq!(cell_grid[cells[1]].potentially_valid_values, CandidateSet::from([2, 3]));
+        assert_eq!(cell_grid[cells[2]].potentially_valid_values, CandidateSet::from([1, 3]));
+    }
+
+    #[test]
+    fn eliminate_nothing_when_no_hidden_subset() {
+        let (mut cell_grid, cells) = first_row();
+
+        let group = CellGroup::new(cells.clone());
+        let some_eliminated = !eliminate_hidden_subsets_in(&mut cell_grid, &group).is_empty();
+
+        assert!(!some_eliminated);
+    }
+
+    #[test]
+    fn solves_puzzle_that_single_candidate_cells_cannot() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut only_single_candidates = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut only_single_candidates);
+        assert!(!only_single_candidates.is_complete());
+
+        // Subsets of more than half the empty cells are left to closed candidate groups, as the naked subsets they match
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let summary = Solver::new(vec![Box::new(SingleCandidateCells), Box::new(HiddenSubsets), Box::new(ClosedCandidateGroups)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        assert!(sudoku.is_complete());
+        assert!(summary.log.steps.iterate().any(|step| step.technique == HiddenSubsets.name()));
+    }
+
+    #[test]
+    fn finds_nothing_in_an_empty_grid_of_the_largest_size() {
+        // Every value fits everywhere, so no set of values stays small enough to look at
+        let mut sudoku = Puzzle::with_shape(Shape::new(5, 5).expect("test shape is valid"));
+
+        assert_eq!(HiddenSubsets.apply(&mut sudoku), StepResult::NoProgress);
+    }
+}
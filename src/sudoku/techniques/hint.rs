@@ -0,0 +1,133 @@
+use crate::pretty::aliases::*;
+
+use crate::sudoku::core::{cell_grid::Coordinate, puzzle::Puzzle, shape::Shape};
+
+use super::{
+    implicitly_solved::eliminate_options_from_groups,
+    solver::{Solver, StepResult},
+    step::{Step, Unit}
+};
+
+// How much of the answer to give away, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintLevel {
+    Technique,
+    Unit,
+    Cell,
+    Explanation,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hint {
+    pub step: Step,
+    pub difficulty: u32,
+    pub shape: Shape,
+}
+
+impl Hint {
+    pub fn describe(&self, level: HintLevel) -> String {
+        let units = self.units();
+
+        return match level {
+            HintLevel::Technique => self.step.technique.clone(),
+            HintLevel::Unit if units.is_empty() => self.describe(HintLevel::Technique),
+            HintLevel::Unit => format!("{} in {}", self.step.technique, units.iterate().map(|unit| unit.to_string()).collect::<Vector<_>>().join(", ")),
+            HintLevel::Cell => match self.target_cell() {
+                Some(cell) => format!("{}, looking at {}", self.describe(HintLevel::Unit), cell),
+                None => self.describe(HintLevel::Unit),
+            },
+            HintLevel::Explanation => self.step.to_string(),
+        };
+    }
+
+    // Where a value goes, or failing that the first cell to lose a candidate
+    pub fn target_cell(&self) -> Option<Coordinate> {
+        return self.step.placed.first().or(self.step.removed.first()).map(|&(coordinate, _)| coordinate);
+    }
+
+    // Not every technique works within units, but the cells it works on may still all be in one
+    fn units(&self) -> Vector<Unit> {
+        if self.step.units.is_empty() {
+            return Unit::shared_by(self.shape, &self.step.cells);
+        }
+        return self.step.units.clone();
+    }
+}
+
+pub fn next_hint(sudoku: &Puzzle) -> Option<Hint> {
+    return next_hint_from(&Solver::logical(), sudoku);
+}
+
+// The first technique in the solver's order that can make progress gives the hint. It works on a copy, so the puzzle is left as it was.
+pub fn next_hint_from(solver: &Solver, sudoku: &Puzzle) -> Option<Hint> {
+
+    let mut copy = sudoku.clone();
+
+    // Crossing off values already placed in a unit isn't worth a hint of its own
+    eliminate_options_from_groups(&mut copy.cell_grid, &copy.rows);
+    eliminate_options_from_groups(&mut copy.cell_grid, &copy.columns);
+    eliminate_options_from_groups(&mut copy.cell_grid, &copy.blocks);
+
+    for technique in solver.techniques() {
+        if let StepResult::Progressed(step) = technique.apply(&mut copy) {
+            return Some(Hint { step, difficulty: technique.difficulty(), shape: sudoku.shape() });
+        }
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{format::serializer::Serializer, techniques::backtracking_brute_force::snapshot_cells};
+
+    use super::*;
+
+    #[test]
+    fn hint_leaves_the_puzzle_alone() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+        let sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let before = snapshot_cells(&sudoku);
+
+        assert!(next_hint(&sudoku).is_some());
+
+        let after = snapshot_cells(&sudoku);
+        for (before, after) in before.iterate().zip(after.iterate()) {
+            assert_eq!(before.value, after.value);
+            assert_eq!(before.potentially_valid_values, after.potentially_valid_values);
+        }
+    }
+
+    #[test]
+    fn each_level_gives_away_more() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+        let sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+
+        let hint = next_hint(&sudoku).expect("the puzzle isn't finished");
+
+        assert_eq!(hint.describe(HintLevel::Technique), "Hidden singles");
+        assert_eq!(hint.describe(HintLevel::Unit), "Hidden singles in column 2");
+        assert_eq!(hint.describe(HintLevel::Cell), "Hidden singles in column 2, looking at r6c2");
+        assert_eq!(hint.describe(HintLevel::Explanation), "Hidden singles in column 2 on r6c2, placing 4 at r6c2");
+    }
+
+    #[test]
+    fn single_candidate_is_found_in_its_cell() {
+        let trivial_puzzle = "534678912672195348198342567859761423426853791713924856961537284287419635345.86179";
+        let sudoku = Serializer::new().new_puzzle(trivial_puzzle).expect("test data is valid");
+
+        let hint = next_hint(&sudoku).expect("one cell is left");
+
+        assert_eq!(hint.step.placed, vec![(Coordinate::new(8, 3), 2)]);
+        assert_eq!(hint.target_cell(), Some(Coordinate::new(8, 3)));
+        assert!(hint.step.removed.is_empty());
+    }
+
+    #[test]
+    fn no_hint_for_a_finished_puzzle() {
+        let solved = "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+        let sudoku = Serializer::new().new_puzzle(solved).expect("test data is valid");
+
+        assert!(next_hint(&sudoku).is_none());
+    }
+}
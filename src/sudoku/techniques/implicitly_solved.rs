@@ -1,47 +1,51 @@
 use crate::pretty::aliases::*;
-use crate::sudoku::core::{consts::PUZZLE_TOTAL_CELL_COUNT, puzzle::Puzzle, validatable_units::CellGroup};
-
-pub fn solve_single_candidate_cells(sudoku: &mut Puzzle) {
-    
-    let mut i = 0;
-    loop {
-        i+=1;
-
-        let pre_iteration_completed_cell_count = sudoku.count_cells_with_value();
-        try_complete_all_cells(sudoku);
-
-        if sudoku.count_cells_with_value() == pre_iteration_completed_cell_count {
-            println!("Finished filling in implicitely solved cells after {} iterations", i);
-            break;
-        }
-
-        if i >= PUZZLE_TOTAL_CELL_COUNT * PUZZLE_TOTAL_CELL_COUNT {
-            println!("Gave up filling in implicitely solved cells after {} iterations", i);
-            // Should never happen, but ensure we terminate
-            break;
-        }
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::CellGrid, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::Step};
+
+pub struct SingleCandidateCells;
+
+impl Technique for SingleCandidateCells {
+    fn name(&self) -> StringSlice<'static> {
+        return "Single candidate cells";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 10;
+    }
+
+    // Tidying up candidates counts as progress too, so replaying the log leaves every candidate as it was
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let step = Step::observe(self.name(), sudoku, |sudoku| {
+            try_complete_all_cells(sudoku);
+            return true;
+        });
+
+        return StepResult::from(step.map(|step| {
+            let placed_cells: Vector<_> = step.placed.iterate().map(|&(coordinate, _)| coordinate).collect();
+            step.with_cells(placed_cells)
+        }));
     }
 }
 
 fn try_complete_all_cells(sudoku: &mut Puzzle){
-    eliminate_options_from_groups(&mut sudoku.rows);
-    eliminate_options_from_groups(&mut sudoku.columns);
-    eliminate_options_from_groups(&mut sudoku.blocks);
-    sudoku.cell_grid.grid.iterate().flatten().for_each(|rc| rc.borrow_mut().try_complete())
+    eliminate_options_from_groups(&mut sudoku.cell_grid, &sudoku.rows);
+    eliminate_options_from_groups(&mut sudoku.cell_grid, &sudoku.columns);
+    eliminate_options_from_groups(&mut sudoku.cell_grid, &sudoku.blocks);
+    sudoku.cell_grid.cells.iter_mut().for_each(|cell| cell.try_complete())
 }
 
-
-fn eliminate_options_from_groups(collection: &mut Vector<CellGroup>){
+pub fn eliminate_options_from_groups(cell_grid: &mut CellGrid, collection: &[CellGroup]){
     for group in collection {
         
-        let used_values: Vector<u8> = group.cells.iterate().filter_map(|rc| rc.borrow().value).collect();
-        group.cells.iterate().for_each(|rc| {rc.borrow_mut().discount_values(&used_values);});
+        let used_values: CandidateSet = group.cells_in(cell_grid).filter_map(|cell| cell.value).collect();
+        group.cells.iterate().for_each(|&coordinate| {cell_grid[coordinate].discount_values(used_values);});
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::sudoku::{core::validatable_units::PuzzleValidator, draw::terminal_print::draw_all_rows, format::serializer::Serializer};
+    use crate::sudoku::{core::validatable_units::PuzzleValidator, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::solver::Solver};
 
     use super::*;
 
@@ -52,12 +56,12 @@ mod tests {
         let mut sudoku = Serializer::new().new_puzzle(trivial_puzzle).expect("the test data should be correct");
 
         assert!(!sudoku.is_complete());
-        assert!(sudoku.cell_grid[8][3].borrow().value.is_none());
+        assert!(sudoku.cell_grid[8][3].value.is_none());
 
-        solve_single_candidate_cells(&mut sudoku);
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
         
         assert!(sudoku.is_complete());
-        assert_eq!(sudoku.cell_grid[8][3].borrow().value.unwrap(), 2);
+        assert_eq!(sudoku.cell_grid[8][3].value.unwrap(), 2);
 
     }
 
@@ -66,9 +70,9 @@ mod tests {
 
         let puzzle = "5834.7.2...7...453.61.258...94.....5.5..63.14..68....7........8.3564..9..792385..";
         let mut sudoku: Puzzle = Serializer::new().new_puzzle(puzzle).expect("the test data should be correct");
-        draw_all_rows(&sudoku.rows);
-        solve_single_candidate_cells(&mut sudoku);
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
 
         assert!(sudoku.is_complete());
     }
@@ -78,9 +82,9 @@ mod tests {
 
         let puzzle = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
         let mut sudoku: Puzzle = Serializer::new().new_puzzle(puzzle).expect("the test data should be correct");
-        draw_all_rows(&sudoku.rows);
-        solve_single_candidate_cells(&mut sudoku);
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
 
         assert!(sudoku.is_complete());
     }
@@ -89,11 +93,11 @@ mod tests {
     fn fills_in_many_blanks_in_medium_puzzle_with_47_spaces() {
         let puzzle = "...97564...13..572.7....8....27....3..7..32..8..6.2..74.9....6.7..8..1.4286.34...";
         let mut sudoku: Puzzle = Serializer::new().new_puzzle(puzzle).expect("the test data should be correct");
-        draw_all_rows(&sudoku.rows);
+        draw_all_rows(&sudoku);
 
         let initial_cell_count = sudoku.count_cells_with_value();
-        solve_single_candidate_cells(&mut sudoku);
-        draw_all_rows(&sudoku.rows);
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
         assert!(sudoku.count_cells_with_value() > initial_cell_count);
         println!("before: {}, after: {}", initial_cell_count, sudoku.count_cells_with_value());
     }
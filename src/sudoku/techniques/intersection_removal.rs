@@ -0,0 +1,135 @@
+use crate::pretty::aliases::*;
+use crate::sudoku::core::{cell_grid::{CellGrid, Coordinate}, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::{Snapshot, Step, Unit}};
+
+pub struct IntersectionRemoval;
+
+impl Technique for IntersectionRemoval {
+    fn name(&self) -> StringSlice<'static> {
+        return "Intersection removal";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 26;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let before = Snapshot::of(&sudoku.cell_grid);
+        let Puzzle { cell_grid, rows, columns, blocks, .. } = sudoku;
+        let (blocks, lines): (Vector<_>, Vector<_>) = Unit::with_groups(rows, columns, blocks).partition(|(unit, _)| matches!(unit, Unit::Block(_)));
+
+        for &(block, block_group) in &blocks {
+        for &(line, line_group) in &lines {
+
+            if !block_group.cells.iterate().any(|&cell| line_group.contains(cell)) {
+                continue;
+            }
+
+            for value in cell_grid.shape.values() {
+                // Pointing: the block needs the value somewhere on this line, so the rest of the line can't have it.
+                // Box/line reduction: the line needs the value somewhere in this block, so the rest of the block can't have it.
+                for ((confined_in, confined_group), (eliminate_from, eliminate_group)) in [((block, block_group), (line, line_group)), ((line, line_group), (block, block_group))] {
+                    if !eliminate_when_confined_to_intersection(cell_grid, confined_group, eliminate_group, value) {
+                        continue;
+                    }
+
+                    // Nothing is taken from the confined cells themselves, so they still show where the value must go
+                    if let Some(step) = Step::since(self.name(), &before, cell_grid) {
+                        let confined_cells: Vector<_> = confined_group.cells.iterate().copied().filter(|&coordinate| cell_grid[coordinate].potentially_valid_values.contains(value)).collect();
+                        return StepResult::Progressed(step.with_units([confined_in, eliminate_from]).with_cells(confined_cells));
+                    }
+                }
+            }
+        }}
+
+        return StepResult::NoProgress;
+    }
+}
+
+fn eliminate_when_confined_to_intersection(cell_grid: &mut CellGrid, confined_in: &CellGroup, eliminate_from: &CellGroup, value: u8) -> bool {
+
+    let cells_with_candidate: Vector<Coordinate> = confined_in
+        .cells
+        .iterate()
+        .copied()
+        .filter(|&coordinate| cell_grid[coordinate].potentially_valid_values.contains(value))
+        .collect();
+
+    if cells_with_candidate.is_empty() || !cells_with_candidate.iterate().all(|&cell| eliminate_from.contains(cell)) {
+        return false;
+    }
+
+    let mut any_eliminated = false;
+
+    for &cell in eliminate_from.cells.iterate().filter(|&&cell| !confined_in.contains(cell)) {
+        any_eliminated = cell_grid[cell].discount_value(value) || any_eliminated;
+    }
+
+    return any_eliminated;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::validatable_units::PuzzleValidator, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    // The first block and the first row of an empty grid, which share their first three cells
+    fn block_and_row() -> (CellGrid, Vector<Coordinate>, Vector<Coordinate>) {
+        let sudoku = Puzzle::default();
+        return (CellGrid::new(), sudoku.blocks[0].cells.clone(), sudoku.rows[0].cells.clone());
+    }
+
+    #[test]
+    fn pointing_removes_value_from_rest_of_line() {
+        let (mut cell_grid, block, row) = block_and_row();
+        block.iterate().skip(3).for_each(|&cell| {cell_grid[cell].discount_value(5);});
+
+        let some_eliminated = eliminate_when_confined_to_intersection(&mut cell_grid, &CellGroup::new(block), &CellGroup::new(row.clone()), 5);
+
+        assert!(some_eliminated);
+        assert!(row.iterate().take(3).all(|&cell| cell_grid[cell].potentially_valid_values.contains(5)));
+        assert!(row.iterate().skip(3).all(|&cell| !cell_grid[cell].potentially_valid_values.contains(5)));
+    }
+
+    #[test]
+    fn box_line_reduction_removes_value_from_rest_of_block() {
+        let (mut cell_grid, block, row) = block_and_row();
+        row.iterate().skip(3).for_each(|&cell| {cell_grid[cell].discount_value(6);});
+
+        let some_eliminated = eliminate_when_confined_to_intersection(&mut cell_grid, &CellGroup::new(row), &CellGroup::new(block.clone()), 6);
+
+        assert!(some_eliminated);
+        assert!(block.iterate().take(3).all(|&cell| cell_grid[cell].potentially_valid_values.contains(6)));
+        assert!(block.iterate().skip(3).all(|&cell| !cell_grid[cell].potentially_valid_values.contains(6)));
+    }
+
+    #[test]
+    fn eliminate_nothing_when_value_also_outside_intersection() {
+        let (mut cell_grid, block, row) = block_and_row();
+
+        let (block, row) = (CellGroup::new(block), CellGroup::new(row));
+
+        let some_eliminated = cell_grid.shape.values().any(|value| {
+            eliminate_when_confined_to_intersection(&mut cell_grid, &block, &row, value) || eliminate_when_confined_to_intersection(&mut cell_grid, &row, &block, value)
+        });
+
+        assert!(!some_eliminated);
+    }
+
+    #[test]
+    fn solves_puzzle_that_single_candidate_cells_cannot() {
+        let test_case = "......9.7...42.18....7.5.261..9.4....5.....4....5.7..992.1.8....34.59...5.7......";
+
+        let mut only_single_candidates = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells)]).solve(&mut only_single_candidates);
+        assert!(!only_single_candidates.is_complete());
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(IntersectionRemoval)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        assert!(sudoku.is_complete());
+    }
+}
@@ -1,3 +1,19 @@
 pub mod implicitly_solved;
 pub mod conjugate_groups;
-pub mod backtracking_brute_force;
\ No newline at end of file
+pub mod backtracking_brute_force;
+pub mod hidden_singles;
+pub mod hidden_subsets;
+pub mod intersection_removal;
+pub mod fish;
+pub mod wings;
+pub mod coloring;
+pub mod single_digit_patterns;
+pub mod chains;
+pub mod uniqueness;
+pub mod als;
+pub mod sue_de_coq;
+pub mod forcing;
+pub mod sat;
+pub mod solver;
+pub mod step;
+pub mod hint;
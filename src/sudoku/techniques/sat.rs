@@ -0,0 +1,407 @@
+use crate::pretty::{aliases::*, iterable::*};
+
+use crate::sudoku::core::{cell_grid::Coordinate, dancing_links::VariantConstraint, puzzle::{Puzzle, SeedGrid}, shape::Shape};
+
+// Literals follow the DIMACS convention: variable n is the literal n when true and -n when false, and there is no variable 0
+pub type Literal = i32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cnf {
+    pub variable_count: usize,
+    pub clauses: Vector<Vector<Literal>>,
+}
+
+impl Cnf {
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.variable_count, self.clauses.len());
+
+        for clause in &self.clauses {
+            for literal in clause {
+                dimacs.push_str(&literal.to_string());
+                dimacs.push(' ');
+            }
+            dimacs.push_str("0\n");
+        }
+
+        return dimacs;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SatResult {
+    // Every variable's literal, positive when it's true
+    Satisfiable(Vector<Literal>),
+    Unsatisfiable,
+}
+
+// One variable per value per cell, true when the cell holds that value
+pub fn variable_for(shape: Shape, coordinate: Coordinate, value: u8) -> Literal {
+    let dimension = shape.dimension();
+    return ((coordinate.row * dimension + coordinate.column) * dimension + value as usize) as Literal;
+}
+
+pub fn encode_puzzle(sudoku: &Puzzle) -> Cnf {
+    return encode_puzzle_with_variants(sudoku, &[]);
+}
+
+// Only placed values are encoded, not the candidates we've eliminated, so the result owes nothing to our own techniques
+pub fn encode_puzzle_with_variants(sudoku: &Puzzle, variants: &[VariantConstraint]) -> Cnf {
+
+    let shape = sudoku.shape();
+    let mut clauses = Vector::new();
+    let coordinates = sudoku.cell_grid.coordinates();
+
+    for &coordinate in &coordinates {
+        let cell_variables: Vector<Literal> = shape.values().map(|value| variable_for(shape, coordinate, value)).collect();
+        add_exactly_one(&mut clauses, &cell_variables);
+
+        if let Some(value) = sudoku.cell_grid[coordinate].value {
+            clauses.push(vec![variable_for(shape, coordinate, value)]);
+        }
+    }
+
+    let mut units: Vector<Vector<Coordinate>> = sudoku.rows
+        .iterate()
+        .chain(sudoku.columns.iterate())
+        .chain(sudoku.blocks.iterate())
+        .map(|group| group.cells.iterate().copied().collect())
+        .collect();
+    units.extend(variants.iterate().map(|variant| variant.cells(shape)));
+
+    for unit in &units {
+        for value in shape.values() {
+            let unit_variables: Vector<Literal> = unit.iterate().map(|&coordinate| variable_for(shape, coordinate, value)).collect();
+
+            // A unit smaller than the puzzle, like an irregular region, only stops values repeating
+            if unit.len() == shape.dimension() {
+                add_exactly_one(&mut clauses, &unit_variables);
+            } else {
+                add_at_most_one(&mut clauses, &unit_variables);
+            }
+        }
+    }
+
+    return Cnf {
+        variable_count: coordinates.len() * shape.dimension(),
+        clauses
+    };
+}
+
+// Reads a model back into a grid, whether it came from solve_cnf or from the output of an external solver
+pub fn seed_from_model(shape: Shape, model: &[Literal]) -> SeedGrid {
+
+    let dimension = shape.dimension();
+    let mut grid = vec![vec![None; dimension]; dimension];
+
+    for &literal in model.iterate().filter(|&&literal| literal > 0) {
+        let index = literal as usize - 1;
+        let cell = index / dimension;
+        grid[cell / dimension][cell % dimension] = Some((index % dimension) as u8 + 1);
+    }
+
+    return grid;
+}
+
+pub fn find_sat_solution(sudoku: &Puzzle) -> Option<SeedGrid> {
+    return match solve_cnf(&encode_puzzle(sudoku)) {
+        SatResult::Satisfiable(model) => Some(seed_from_model(sudoku.shape(), &model)),
+        SatResult::Unsatisfiable => None,
+    };
+}
+
+fn add_exactly_one(clauses: &mut Vector<Vector<Literal>>, variables: &[Literal]) {
+    clauses.push(variables.to_vec());
+    add_at_most_one(clauses, variables);
+}
+
+fn add_at_most_one(clauses: &mut Vector<Vector<Literal>>, variables: &[Literal]) {
+    for pair in variables.to_vec().combinations_of_size(2) {
+        clauses.push(vec![-pair[0], -pair[1]]);
+    }
+}
+
+// A DPLL search with two watched literals per clause. Each clause is watched through its first two literals, and only
+// needs looking at again when one of those becomes false, which keeps unit propagation cheap on the thousands of
+// clauses a puzzle turns into. Backtracking is chronological: undo to the last decision not yet tried both ways.
+struct DpllSolver {
+    clauses: Vector<Vector<Literal>>,
+    // Indexed by variable: 1 true, -1 false, 0 unassigned
+    assignment: Vector<i8>,
+    // Indexed by literal_index: the clauses watching that literal
+    watches: Vector<Vector<usize>>,
+    trail: Vector<Literal>,
+    propagated: usize,
+    // Where each decision sits on the trail, and whether its other branch has been tried yet
+    decisions: Vector<(usize, bool)>,
+}
+
+impl DpllSolver {
+    fn new(cnf: &Cnf) -> Self {
+        return Self {
+            clauses: Vector::new(),
+            assignment: vec![0; cnf.variable_count + 1],
+            watches: vec![Vector::new(); 2 * (cnf.variable_count + 1)],
+            trail: Vector::new(),
+            propagated: 0,
+            decisions: Vector::new(),
+        };
+    }
+
+    fn value_of(&self, literal: Literal) -> i8 {
+        let value = self.assignment[literal.unsigned_abs() as usize];
+        return if literal > 0 { value } else { -value };
+    }
+
+    fn assign(&mut self, literal: Literal) {
+        self.assignment[literal.unsigned_abs() as usize] = if literal > 0 { 1 } else { -1 };
+        self.trail.push(literal);
+    }
+
+    // Gives false when the clauses contradict each other before any decision is made
+    fn add_clauses(&mut self, clauses: &[Vector<Literal>]) -> bool {
+        for clause in clauses {
+            let mut clause = clause.clone();
+            clause.sort();
+            clause.dedup();
+
+            match clause.len() {
+                0 => return false,
+                1 => match self.value_of(clause[0]) {
+                    -1 => return false,
+                    0 => self.assign(clause[0]),
+                    _ => (),
+                },
+                _ => {
+                    let index = self.clauses.len();
+                    self.watches[literal_index(clause[0])].push(index);
+                    self.watches[literal_index(clause[1])].push(index);
+                    self.clauses.push(clause);
+                }
+            }
+        }
+
+        return true;
+    }
+
+    // Gives false on a conflict
+    fn propagate(&mut self) -> bool {
+
+        while self.propagated < self.trail.len() {
+            let falsified = -self.trail[self.propagated];
+            self.propagated += 1;
+
+            let watching = std::mem::take(&mut self.watches[literal_index(falsified)]);
+            let mut keep = Vector::with_capacity(watching.len());
+            let mut conflict = false;
+
+            for (position, &clause_index) in watching.iterate().enumerate() {
+
+                if conflict {
+                    keep.extend_from_slice(&watching[position..]);
+                    break;
+                }
+
+                let clause = &mut self.clauses[clause_index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+
+                let other = clause[0];
+                let replacement = (2..clause.len()).find(|&index| {
+                    let literal = clause[index];
+                    let value = self.assignment[literal.unsigned_abs() as usize];
+                    (if literal > 0 { value } else { -value }) != -1
+                });
+
+                if let Some(index) = replacement {
+                    clause.swap(1, index);
+                    let new_watch = clause[1];
+                    self.watches[literal_index(new_watch)].push(clause_index);
+                    continue;
+                }
+
+                keep.push(clause_index);
+
+                match self.value_of(other) {
+                    -1 => conflict = true,
+                    0 => self.assign(other),
+                    _ => (),
+                }
+            }
+
+            self.watches[literal_index(falsified)] = keep;
+
+            if conflict {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    // Branch on the unsatisfied clause with fewest open literals, as that is where a wrong guess shows up soonest
+    fn choose_literal(&self) -> Option<Literal> {
+
+        let mut best: Option<(usize, Literal)> = None;
+
+        for clause in &self.clauses {
+            if clause.iterate().any(|&literal| self.value_of(literal) == 1) {
+                continue;
+            }
+
+            let open: Vector<Literal> = clause.iterate().copied().filter(|&literal| self.value_of(literal) == 0).collect();
+            if !open.is_empty() && best.is_none_or(|(count, _)| open.len() < count) {
+                best = Some((open.len(), open[0]));
+            }
+        }
+
+        return best.map(|(_, literal)| literal);
+    }
+
+    fn undo_to(&mut self, trail_length: usize) {
+        while self.trail.len() > trail_length {
+            let literal = self.trail.pop().expect("trail is longer than the target length");
+            self.assignment[literal.unsigned_abs() as usize] = 0;
+        }
+        self.propagated = trail_length;
+    }
+
+    fn solve(&mut self) -> SatResult {
+
+        loop {
+            if self.propagate() {
+                match self.choose_literal() {
+                    Some(literal) => {
+                        self.decisions.push((self.trail.len(), false));
+                        self.assign(literal);
+                    },
+                    None => return SatResult::Satisfiable(self.model()),
+                }
+                continue;
+            }
+
+            // Undo decisions until one still has its other branch left to try
+            loop {
+                let Some((trail_length, tried_both)) = self.decisions.pop() else {
+                    return SatResult::Unsatisfiable;
+                };
+
+                let decided = self.trail[trail_length];
+                self.undo_to(trail_length);
+
+                if !tried_both {
+                    self.decisions.push((trail_length, true));
+                    self.assign(-decided);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Variables no clause needed are free, so they're reported false
+    fn model(&self) -> Vector<Literal> {
+        return (1..self.assignment.len()).map(|variable| if self.assignment[variable] == 1 { variable as Literal } else { -(variable as Literal) }).collect();
+    }
+}
+
+fn literal_index(literal: Literal) -> usize {
+    let variable = literal.unsigned_abs() as usize;
+    return 2 * variable + usize::from(literal < 0);
+}
+
+pub fn solve_cnf(cnf: &Cnf) -> SatResult {
+
+    let mut solver = DpllSolver::new(cnf);
+    if !solver.add_clauses(&cnf.clauses) {
+        return SatResult::Unsatisfiable;
+    }
+
+    return solver.solve();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::{consts::{PUZZLE_DIMENTION, PUZZLE_MAXIMUM_VALUE}, dancing_links::find_exact_cover_solutions, validatable_units::PuzzleValidator}, format::serializer::Serializer};
+
+    use super::*;
+
+    fn is_satisfied_by(cnf: &Cnf, model: &[Literal]) -> bool {
+        return cnf.clauses.iterate().all(|clause| clause.iterate().any(|literal| model.contains(literal)));
+    }
+
+    #[test]
+    fn dimacs_has_header_and_zero_terminated_clauses() {
+        let cnf = Cnf { variable_count: 3, clauses: vec![vec![1, -2], vec![3]] };
+
+        assert_eq!(cnf.to_dimacs(), "p cnf 3 2\n1 -2 0\n3 0\n");
+    }
+
+    #[test]
+    fn encoding_has_variable_per_value_per_cell_and_unit_clause_per_value() {
+        let sudoku = Serializer::new().new_puzzle("5................................................................................").expect("test data is valid");
+
+        let cnf = encode_puzzle(&sudoku);
+
+        assert_eq!(cnf.variable_count, 729);
+        assert!(cnf.clauses.contains(&vec![variable_for(Shape::classic(), Coordinate::new(0, 0), 5)]));
+        assert_eq!(cnf.clauses.iterate().filter(|clause| clause.len() == 1).count(), 1);
+        assert!(cnf.to_dimacs().starts_with(&format!("p cnf 729 {}\n", cnf.clauses.len())));
+    }
+
+    #[test]
+    fn solves_small_formulas() {
+        let satisfiable = Cnf { variable_count: 3, clauses: vec![vec![1, 2], vec![-1], vec![-2, 3]] };
+        let SatResult::Satisfiable(model) = solve_cnf(&satisfiable) else {
+            panic!("formula is satisfiable");
+        };
+        assert_eq!(model, vec![-1, 2, 3]);
+        assert!(is_satisfied_by(&satisfiable, &model));
+
+        let unsatisfiable = Cnf { variable_count: 2, clauses: vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]] };
+        assert_eq!(solve_cnf(&unsatisfiable), SatResult::Unsatisfiable);
+    }
+
+    #[test]
+    fn agrees_with_exact_cover_on_hard_puzzle() {
+        let test_case = "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..";
+        let sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+
+        let solution = find_sat_solution(&sudoku).expect("puzzle has a solution");
+
+        assert!(Puzzle::new(&solution).expect("test data is valid").is_complete());
+        assert_eq!(vec![solution], find_exact_cover_solutions(Shape::classic(), &sudoku.to_seed(), 1));
+    }
+
+    #[test]
+    fn encodes_other_sizes() {
+        let serializer = Serializer::new();
+        let sudoku = serializer.new_puzzle("5......24......6...31..5......1...56").expect("test data is valid");
+
+        assert_eq!(encode_puzzle(&sudoku).variable_count, 216);
+        assert_eq!(find_sat_solution(&sudoku), Some(serializer.new_puzzle("516342324561452613631425265134143256").expect("test data is valid").to_seed()));
+    }
+
+    #[test]
+    fn unsatisfiable_when_givens_clash() {
+        let sudoku = Serializer::new().new_puzzle("55..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79").expect("test data is valid");
+
+        assert!(find_sat_solution(&sudoku).is_none());
+    }
+
+    #[test]
+    fn variant_regions_are_respected() {
+        let sudoku = Puzzle::default();
+        let region = VariantConstraint::Region(vec![Coordinate::new(0, 0), Coordinate::new(4, 4), Coordinate::new(8, 8)]);
+
+        let cnf = encode_puzzle_with_variants(&sudoku, &[VariantConstraint::MainDiagonal, region]);
+        let SatResult::Satisfiable(model) = solve_cnf(&cnf) else {
+            panic!("an empty puzzle with a diagonal has solutions");
+        };
+        let solution = seed_from_model(Shape::classic(), &model);
+
+        let mut diagonal: Vector<u8> = (0..PUZZLE_DIMENTION).filter_map(|i| solution[i][i]).collect();
+        diagonal.sort();
+        assert_eq!(diagonal, (1..=PUZZLE_MAXIMUM_VALUE).collect::<Vector<u8>>());
+        assert!(Puzzle::new(&solution).expect("test data is valid").is_complete());
+    }
+}
@@ -0,0 +1,286 @@
+use crate::pretty::aliases::*;
+
+use crate::sudoku::core::{cell_grid::Coordinate, puzzle::Puzzle};
+
+use super::{coloring::conjugate_pairs_in, solver::{StepResult, Technique}, step::Step};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SingleDigitPatternKind {
+    Skyscraper,
+    TwoStringKite,
+    EmptyRectangle,
+}
+
+#[derive(Debug)]
+pub struct SingleDigitPattern {
+    pub kind: SingleDigitPatternKind,
+    pub value: u8,
+    pub cells: Vector<Coordinate>,
+    pub eliminations: Vector<Coordinate>,
+}
+
+pub struct SingleDigitPatterns;
+
+impl Technique for SingleDigitPatterns {
+    fn name(&self) -> StringSlice<'static> {
+        return "Single digit patterns";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 44;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        for value in sudoku.shape().values() {
+            let patterns = find_single_digit_patterns(sudoku, value);
+            let pattern_cells: Vector<_> = patterns.iterate().flat_map(|pattern| pattern.cells.clone()).collect();
+
+            if let Some(step) = Step::observe(self.name(), sudoku, |sudoku| eliminate_single_digit_patterns(sudoku, value, patterns)) {
+                return StepResult::Progressed(step.with_cells(pattern_cells));
+            }
+        }
+
+        return StepResult::NoProgress;
+    }
+}
+
+fn eliminate_single_digit_patterns(sudoku: &mut Puzzle, value: u8, patterns: Vector<SingleDigitPattern>) -> bool {
+
+    let mut any_eliminated = false;
+
+    for pattern in patterns {
+        for coordinate in pattern.eliminations {
+            any_eliminated = sudoku.cell_grid[coordinate].discount_value(value) || any_eliminated;
+        }
+    }
+
+    return any_eliminated;
+}
+
+// Only patterns that would eliminate something are returned
+pub fn find_single_digit_patterns(sudoku: &Puzzle, value: u8) -> Vector<SingleDigitPattern> {
+
+    let mut patterns = find_linked_strong_links(sudoku, value);
+    patterns.extend(find_empty_rectangles(sudoku, value));
+    return patterns;
+}
+
+// Skyscrapers and kites are both two strong links, where one end of the first sees one end of the second.
+// Those two ends can't both hold the value, so one of the far ends must, and anything seeing both far ends loses it.
+// Both strong links in parallel lines, joined along a cover line, is a skyscraper; a row and a column joined in a block is a kite.
+fn find_linked_strong_links(sudoku: &Puzzle, value: u8) -> Vector<SingleDigitPattern> {
+
+    let row_links = conjugate_pairs_in(sudoku, &sudoku.rows, value);
+    let column_links = conjugate_pairs_in(sudoku, &sudoku.columns, value);
+
+    let mut patterns = Vector::new();
+
+    for (first_links, second_links, kind) in [
+        (&row_links, &row_links, SingleDigitPatternKind::Skyscraper),
+        (&column_links, &column_links, SingleDigitPatternKind::Skyscraper),
+        (&row_links, &column_links, SingleDigitPatternKind::TwoStringKite),
+    ] {
+        for (first_index, &first_link) in first_links.iterate().enumerate() {
+        for (second_index, &second_link) in second_links.iterate().enumerate() {
+
+            // Parallel lines are paired once, rather than once each way round
+            if kind == SingleDigitPatternKind::Skyscraper && second_index <= first_index {
+                continue;
+            }
+
+            for (joined_first, far_first) in [first_link, (first_link.1, first_link.0)] {
+            for (joined_second, far_second) in [second_link, (second_link.1, second_link.0)] {
+
+                if [joined_first, far_first].iterate().any(|end| *end == joined_second || *end == far_second) {
+                    continue;
+                }
+
+                let is_joined = match kind {
+                    SingleDigitPatternKind::Skyscraper => joined_first.row == joined_second.row || joined_first.column == joined_second.column,
+                    _ => share_block(sudoku, joined_first, joined_second),
+                };
+
+                // Far ends lining up as well would be an X-Wing, which has nothing left outside itself to remove
+                let is_x_wing = far_first.row == far_second.row || far_first.column == far_second.column;
+
+                if !is_joined || (kind == SingleDigitPatternKind::Skyscraper && is_x_wing) {
+                    continue;
+                }
+
+                let eliminations = cells_seeing_all(sudoku, value, &[far_first, far_second]);
+                if eliminations.is_empty() {
+                    continue;
+                }
+
+                patterns.push(SingleDigitPattern {
+                    kind,
+                    value,
+                    cells: vec![far_first, joined_first, joined_second, far_second],
+                    eliminations
+                });
+            }}
+        }}
+    }
+
+    return patterns;
+}
+
+// An empty rectangle is a block whose candidates all lie on one row and one column of it. Take a strong link in a column
+// outside the block, with one end on the empty rectangle's row. If the other end's row held the value where it crosses the
+// empty rectangle's column, the strong link would put the value on the empty rectangle's row, leaving the block nowhere for it.
+// The same works with rows and columns swapped.
+fn find_empty_rectangles(sudoku: &Puzzle, value: u8) -> Vector<SingleDigitPattern> {
+
+    let row_links = conjugate_pairs_in(sudoku, &sudoku.rows, value);
+    let column_links = conjugate_pairs_in(sudoku, &sudoku.columns, value);
+
+    let mut patterns = Vector::new();
+
+    for block in &sudoku.blocks {
+
+        let block_coordinates = &block.cells;
+        let candidate_cells: Vector<Coordinate> = block_coordinates.iterate().filter(|&&coordinate| has_candidate(sudoku, coordinate, value)).copied().collect();
+
+        if candidate_cells.len() < 2 {
+            continue;
+        }
+
+        for &hinge in block_coordinates {
+
+            let on_row_only = candidate_cells.iterate().any(|cell| cell.row == hinge.row && cell.column != hinge.column);
+            let on_column_only = candidate_cells.iterate().any(|cell| cell.column == hinge.column && cell.row != hinge.row);
+            let within_row_and_column = candidate_cells.iterate().all(|cell| cell.row == hinge.row || cell.column == hinge.column);
+
+            if !on_row_only || !on_column_only || !within_row_and_column {
+                continue;
+            }
+
+            for &(first, second) in &column_links {
+            for (on_hinge_line, other_end) in [(first, second), (second, first)] {
+                let target = Coordinate::new(other_end.row, hinge.column);
+
+                if on_hinge_line.row != hinge.row || block_coordinates.iterate().any(|cell| cell.column == on_hinge_line.column || cell.row == other_end.row) {
+                    continue;
+                }
+
+                if has_candidate(sudoku, target, value) {
+                    patterns.push(empty_rectangle(value, &candidate_cells, [on_hinge_line, other_end], target));
+                }
+            }}
+
+            for &(first, second) in &row_links {
+            for (on_hinge_line, other_end) in [(first, second), (second, first)] {
+                let target = Coordinate::new(hinge.row, other_end.column);
+
+                if on_hinge_line.column != hinge.column || block_coordinates.iterate().any(|cell| cell.row == on_hinge_line.row || cell.column == other_end.column) {
+                    continue;
+                }
+
+                if has_candidate(sudoku, target, value) {
+                    patterns.push(empty_rectangle(value, &candidate_cells, [on_hinge_line, other_end], target));
+                }
+            }}
+        }
+    }
+
+    return patterns;
+}
+
+fn empty_rectangle(value: u8, rectangle_cells: &[Coordinate], strong_link: [Coordinate; 2], target: Coordinate) -> SingleDigitPattern {
+    let mut cells = rectangle_cells.to_vec();
+    cells.extend(strong_link);
+
+    return SingleDigitPattern {
+        kind: SingleDigitPatternKind::EmptyRectangle,
+        value,
+        cells,
+        eliminations: vec![target]
+    };
+}
+
+fn cells_seeing_all(sudoku: &Puzzle, value: u8, must_see: &[Coordinate]) -> Vector<Coordinate> {
+    return sudoku.cell_grid
+        .coordinates()
+        .into_iter()
+        .filter(|coordinate| !must_see.contains(coordinate) && has_candidate(sudoku, *coordinate, value))
+        .filter(|&coordinate| must_see.iterate().all(|&other| sudoku.sees(coordinate, other)))
+        .collect();
+}
+
+fn share_block(sudoku: &Puzzle, first: Coordinate, second: Coordinate) -> bool {
+    return sudoku.blocks.iterate().any(|block| block.contains(first) && block.contains(second));
+}
+
+fn has_candidate(sudoku: &Puzzle, coordinate: Coordinate, value: u8) -> bool {
+    return sudoku.cell_grid[coordinate].potentially_valid_values.contains(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::core::consts::PUZZLE_DIMENTION;
+
+    use super::*;
+
+    fn keep_value_in_row_only_at(sudoku: &mut Puzzle, row: usize, value: u8, columns: &[usize]) {
+        (0..PUZZLE_DIMENTION).filter(|column| !columns.contains(column)).for_each(|column| {sudoku.cell_grid[row][column].discount_value(value);});
+    }
+
+    fn keep_value_in_column_only_at(sudoku: &mut Puzzle, column: usize, value: u8, rows: &[usize]) {
+        (0..PUZZLE_DIMENTION).filter(|row| !rows.contains(row)).for_each(|row| {sudoku.cell_grid[row][column].discount_value(value);});
+    }
+
+    #[test]
+    fn skyscraper_removes_value_from_cells_seeing_both_far_ends() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[1, 4]);
+        keep_value_in_row_only_at(&mut sudoku, 4, 1, &[1, 5]);
+
+        let patterns = find_single_digit_patterns(&sudoku, 1);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].kind, SingleDigitPatternKind::Skyscraper);
+        let mut eliminations = patterns[0].eliminations.clone();
+        eliminations.sort();
+        assert_eq!(eliminations, vec![Coordinate::new(1, 5), Coordinate::new(2, 5), Coordinate::new(3, 4), Coordinate::new(5, 4)]);
+
+        assert_ne!(SingleDigitPatterns.apply(&mut sudoku), StepResult::NoProgress);
+        assert!(!has_candidate(&sudoku, Coordinate::new(2, 5), 1));
+    }
+
+    #[test]
+    fn two_string_kite_removes_value_where_far_ends_cross() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[1, 6]);
+        keep_value_in_column_only_at(&mut sudoku, 0, 1, &[2, 7]);
+
+        let patterns = find_single_digit_patterns(&sudoku, 1);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].kind, SingleDigitPatternKind::TwoStringKite);
+        assert_eq!(patterns[0].eliminations, vec![Coordinate::new(7, 6)]);
+    }
+
+    #[test]
+    fn empty_rectangle_removes_value_where_strong_link_crosses_its_column() {
+        let mut sudoku = Puzzle::default();
+        for (row, column) in [(0, 0), (0, 2), (2, 0), (2, 2)] {
+            sudoku.cell_grid[row][column].discount_value(1);
+        }
+        keep_value_in_column_only_at(&mut sudoku, 5, 1, &[1, 6]);
+
+        let patterns = find_single_digit_patterns(&sudoku, 1);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].kind, SingleDigitPatternKind::EmptyRectangle);
+        assert_eq!(patterns[0].eliminations, vec![Coordinate::new(6, 1)]);
+    }
+
+    #[test]
+    fn no_pattern_when_strong_links_form_x_wing() {
+        let mut sudoku = Puzzle::default();
+        keep_value_in_row_only_at(&mut sudoku, 0, 1, &[1, 4]);
+        keep_value_in_row_only_at(&mut sudoku, 4, 1, &[1, 4]);
+
+        assert!(find_single_digit_patterns(&sudoku, 1).is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{pretty::aliases::*, sudoku::core::validatable_units::PuzzleValidator};
+
+use crate::sudoku::core::puzzle::Puzzle;
+
+use super::{
+    als::AlmostLockedSets,
+    backtracking_brute_force::BruteForce,
+    chains::Chains,
+    coloring::Coloring,
+    conjugate_groups::ClosedCandidateGroups,
+    fish::Fish,
+    forcing::{ForcingChains, DEFAULT_FORCING_DEPTH},
+    hidden_singles::HiddenSingles,
+    hidden_subsets::HiddenSubsets,
+    implicitly_solved::SingleCandidateCells,
+    intersection_removal::IntersectionRemoval,
+    single_digit_patterns::SingleDigitPatterns,
+    step::{SolveLog, Step},
+    sue_de_coq::SueDeCoqTechnique,
+    wings::Wings
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepResult {
+    // Something was placed or eliminated, so simpler techniques may now find more
+    Progressed(Step),
+    NoProgress,
+}
+
+impl From<Option<Step>> for StepResult {
+    fn from(step: Option<Step>) -> Self {
+        return match step {
+            Some(step) => StepResult::Progressed(step),
+            None => StepResult::NoProgress,
+        };
+    }
+}
+
+pub trait Technique {
+    fn name(&self) -> StringSlice<'static>;
+
+    // Roughly how hard a person finds the technique; the hardest one a solve needs is what grades the puzzle
+    fn difficulty(&self) -> u32;
+
+    // Makes one round of deductions, leaving the simpler techniques to follow up on them
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult;
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SolveSummary {
+    pub log: SolveLog,
+    pub difficulty: u32,
+    pub is_complete: bool,
+}
+
+pub struct Solver {
+    techniques: Vector<Box<dyn Technique>>,
+}
+
+impl Solver {
+    // Techniques are tried in the order given, so the simplest should come first
+    pub fn new(techniques: Vector<Box<dyn Technique>>) -> Self {
+        return Self { techniques };
+    }
+
+    pub fn techniques(&self) -> &[Box<dyn Technique>] {
+        return &self.techniques;
+    }
+
+    // Every time a technique makes progress we go back to the simplest, so harder ones are only reached when nothing easier works
+    pub fn solve(&self, sudoku: &mut Puzzle) -> SolveSummary {
+
+        let mut summary = SolveSummary::default();
+
+        let mut i = 0;
+        loop {
+            i+=1;
+
+            let Some((technique, step)) = self.techniques.iterate().find_map(|technique| match technique.apply(sudoku) {
+                StepResult::Progressed(step) => Some((technique, step)),
+                StepResult::NoProgress => None,
+            }) else {
+                break;
+            };
+
+            summary.log.push(step);
+            summary.difficulty = summary.difficulty.max(technique.difficulty());
+
+            if i >= sudoku.shape().cell_count() * sudoku.shape().cell_count() {
+                // Should never happen, but ensure we terminate
+                break;
+            }
+        }
+
+        summary.is_complete = sudoku.is_complete();
+        return summary;
+    }
+
+    // Every technique that reasons its way to an answer, simplest first, leaving out brute force and uniqueness patterns.
+    // Uniqueness patterns are only sound once the puzzle is known to have a single solution.
+    pub fn logical() -> Self {
+        return Solver::new(vec![
+            Box::new(SingleCandidateCells),
+            Box::new(HiddenSingles),
+            Box::new(IntersectionRemoval),
+            Box::new(ClosedCandidateGroups),
+            Box::new(HiddenSubsets),
+            Box::new(Fish),
+            Box::new(Wings),
+            Box::new(SingleDigitPatterns),
+            Box::new(Coloring),
+            Box::new(SueDeCoqTechnique),
+            Box::new(Chains),
+            Box::new(AlmostLockedSets),
+            Box::new(ForcingChains { depth_limit: DEFAULT_FORCING_DEPTH }),
+        ]);
+    }
+}
+
+// The logical techniques with brute force after them, so the solver always finishes a puzzle that can be finished
+impl Default for Solver {
+    fn default() -> Self {
+        let mut solver = Solver::logical();
+        solver.techniques.push(Box::new(BruteForce));
+        return solver;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::{consts::PUZZLE_TOTAL_CELL_COUNT, shape::Shape}, format::serializer::Serializer, techniques::backtracking_brute_force::find_solutions};
+
+    use super::*;
+
+    struct AlwaysProgresses;
+
+    impl Technique for AlwaysProgresses {
+        fn name(&self) -> StringSlice<'static> {
+            return "Always progresses";
+        }
+
+        fn difficulty(&self) -> u32 {
+            return 1;
+        }
+
+        fn apply(&self, _: &mut Puzzle) -> StepResult {
+            return StepResult::Progressed(Step::new(self.name()));
+        }
+    }
+
+    #[test]
+    fn default_techniques_get_harder() {
+        let difficulties: Vector<u32> = Solver::default().techniques().iterate().map(|technique| technique.difficulty()).collect();
+
+        assert!(difficulties.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn easy_puzzle_only_needs_singles() {
+        let test_case = "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+
+        let summary = Solver::default().solve(&mut sudoku);
+
+        assert!(summary.is_complete);
+        assert!(summary.log.steps.iterate().all(|step| step.technique == SingleCandidateCells.name() || step.technique == HiddenSingles.name()));
+        assert!(summary.difficulty <= HiddenSingles.difficulty());
+    }
+
+    #[test]
+    fn restarts_from_the_simplest_after_progress() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let solution = find_solutions(&mut solved, 1).remove(0);
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let summary = Solver::new(vec![Box::new(SingleCandidateCells), Box::new(HiddenSingles), Box::new(BruteForce)]).solve(&mut sudoku);
+
+        assert!(summary.is_complete);
+        assert_eq!(sudoku.to_seed(), solution);
+        assert_eq!(summary.difficulty, BruteForce.difficulty());
+        // Once brute force has filled the grid, there is nothing left for the others to do
+        assert_eq!(summary.log.steps.last().map(|step| step.technique.as_str()), Some(BruteForce.name()));
+        assert_eq!(summary.log.steps.iterate().filter(|step| step.technique == BruteForce.name()).count(), 1);
+    }
+
+    #[test]
+    fn solves_other_sizes() {
+        let test_cases = [
+            (Shape::new(2, 2).expect("test shape is valid"), ".24...........13", "3241413213242413"),
+            (Shape::new(2, 3).expect("test shape is valid"), "5......24......6...31..5......1...56", "516342324561452613631425265134143256"),
+            (Shape::new(3, 2).expect("test shape is valid"), "....3.56.......1...4.....1...62.4...", "126534563421432165641352315246254613"),
+            (Shape::new(4, 4).expect("test shape is valid"),
+                "E93G6D.B...4.2C.C.2.......9.D...FA.1.5..D.8..3E9..6.3G.E....1..A.E.7...6..F.852C6...G.E...C591.F4F19...2A.BD..3..C.8...47..G....D.AF...G..28E.....7.A.6.E.4...5.5.....4..G...A..1.9E..2.F..AC7.3.5.6E.19.7GC4..D....F..A...E6.85.....2G7.8..3...91.3..58.ADF.C7.",
+                "E93G6D8B1FA452C7C72541AFGE93D6B8FA41257CDB86G3E9B86D3G9E5C7214FA3EG7DAB694F1852C6BDAG7E382C5914F4F1958C2A6BD7G3E2C5819F473EGAD6BD6AF7C3GB528E914G37CAF6DE149B852528B9E41CG37FAD6149E8B25FD6AC7G385B6E31927GC4FAD7GC2F4DA391E6B85ADF4C2G7685B3E9191E3B6584ADF2C7G"),
+        ];
+
+        let serializer = Serializer::new();
+
+        for (shape, test_case, expected) in test_cases {
+            let mut sudoku = serializer.new_puzzle_with_shape(shape, test_case).expect("test data is valid");
+
+            let summary = Solver::default().solve(&mut sudoku);
+
+            assert!(summary.is_complete);
+            assert_eq!(serializer.serialize(&sudoku), expected);
+        }
+    }
+
+    #[test]
+    fn solves_largest_size() {
+        let shape = Shape::new(5, 5).expect("test shape is valid");
+        let dimension = shape.dimension();
+
+        // Rows of a solved grid shifted along by a block's width, then by one more at the end of each band.
+        // With one cell missing from every row and column, each has only one value left.
+        let mut sudoku = Puzzle::with_shape(shape);
+        for row in 0..dimension {
+        for column in 0..dimension {
+            if row != column {
+                let value = (shape.block_width * (row % shape.block_height) + row / shape.block_height + column) % dimension + 1;
+                sudoku.cell_grid[row][column].set_value(value as u8);
+            }
+        }}
+        assert!(sudoku.is_valid());
+
+        let summary = Solver::default().solve(&mut sudoku);
+
+        assert!(summary.is_complete);
+        assert_eq!(summary.difficulty, SingleCandidateCells.difficulty());
+    }
+
+    #[test]
+    fn gives_up_on_a_technique_that_never_stops() {
+        let mut sudoku = Puzzle::default();
+
+        let summary = Solver::new(vec![Box::new(AlwaysProgresses)]).solve(&mut sudoku);
+
+        assert_eq!(summary.log.steps.len(), PUZZLE_TOTAL_CELL_COUNT * PUZZLE_TOTAL_CELL_COUNT);
+        assert!(!summary.is_complete);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::pretty::aliases::*;
+use std::fmt;
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::{CellGrid, Coordinate}, puzzle::Puzzle, shape::Shape, validatable_units::CellGroup};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Block(usize),
+}
+
+impl Unit {
+    // In the same order as the puzzle's own rows, columns and blocks
+    pub fn all(shape: Shape) -> Vector<Unit> {
+        return (0..shape.dimension()).map(Unit::Row)
+            .chain((0..shape.dimension()).map(Unit::Column))
+            .chain((0..shape.dimension()).map(Unit::Block))
+            .collect();
+    }
+
+    // Each unit next to its group, in the same order as all. Only the unit tables are borrowed, so the grid can be
+    // changed while going through them.
+    pub fn with_groups<'a>(rows: &'a [CellGroup], columns: &'a [CellGroup], blocks: &'a [CellGroup]) -> impl Iterator<Item = (Unit, &'a CellGroup)> {
+        return rows.iterate().enumerate().map(|(index, group)| (Unit::Row(index), group))
+            .chain(columns.iterate().enumerate().map(|(index, group)| (Unit::Column(index), group)))
+            .chain(blocks.iterate().enumerate().map(|(index, group)| (Unit::Block(index), group)));
+    }
+
+    pub fn containing(shape: Shape, coordinate: Coordinate) -> [Unit; 3] {
+        return [Unit::Row(coordinate.row), Unit::Column(coordinate.column), Unit::Block(shape.block_of(coordinate))];
+    }
+
+    // Every unit that all of the cells are in, so nothing for cells that don't line up
+    pub fn shared_by(shape: Shape, coordinates: &[Coordinate]) -> Vector<Unit> {
+        let Some(&first) = coordinates.first() else {
+            return Vector::new();
+        };
+
+        return Unit::containing(shape, first)
+            .into_iter()
+            .filter(|unit| coordinates.iterate().all(|&coordinate| Unit::containing(shape, coordinate).contains(unit)))
+            .collect();
+    }
+
+    pub fn group(self, sudoku: &Puzzle) -> &CellGroup {
+        return match self {
+            Unit::Row(index) => &sudoku.rows[index],
+            Unit::Column(index) => &sudoku.columns[index],
+            Unit::Block(index) => &sudoku.blocks[index],
+        };
+    }
+
+    pub fn cells(self, sudoku: &Puzzle) -> Vector<Coordinate> {
+        return self.group(sudoku).cells.clone();
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Unit::Row(index) => write!(f, "row {}", index + 1),
+            Unit::Column(index) => write!(f, "column {}", index + 1),
+            Unit::Block(index) => write!(f, "block {}", index + 1),
+        };
+    }
+}
+
+// One deduction: the cells and units that justify it, and what it changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub technique: String,
+    pub cells: Vector<Coordinate>,
+    pub units: Vector<Unit>,
+    pub removed: Vector<(Coordinate, u8)>,
+    pub placed: Vector<(Coordinate, u8)>,
+}
+
+impl Step {
+    pub fn new(technique: StringSlice) -> Self {
+        return Self {
+            technique: technique.to_string(),
+            cells: Vector::new(),
+            units: Vector::new(),
+            removed: Vector::new(),
+            placed: Vector::new(),
+        };
+    }
+
+    // Runs a deduction and records exactly what it changed, so a technique only has to say why it made the change.
+    // Gives nothing when the puzzle came out the same.
+    pub fn observe(technique: StringSlice, sudoku: &mut Puzzle, deduce: impl FnOnce(&mut Puzzle) -> bool) -> Option<Step> {
+
+        let before = Snapshot::of(&sudoku.cell_grid);
+        if !deduce(sudoku) {
+            return None;
+        }
+
+        return Step::since(technique, &before, &sudoku.cell_grid);
+    }
+
+    // What changed in the grid since the snapshot was taken. A technique that tries many places in turn can take one
+    // snapshot up front, as nothing changes until a deduction is made.
+    pub fn since(technique: StringSlice, before: &Snapshot, cell_grid: &CellGrid) -> Option<Step> {
+
+        let mut step = Step::new(technique);
+
+        for &(coordinate, (value_before, candidates_before)) in &before.cells {
+            let cell = &cell_grid[coordinate];
+            let (value_after, candidates_after) = (cell.value, cell.potentially_valid_values);
+
+            match (value_before, value_after) {
+                (None, Some(value)) => step.placed.push((coordinate, value)),
+                (None, None) => step.removed.extend((candidates_before - candidates_after).into_iter().map(|value| (coordinate, value))),
+                _ => (),
+            }
+        }
+
+        if step.placed.is_empty() && step.removed.is_empty() {
+            return None;
+        }
+
+        return Some(step);
+    }
+
+    pub fn with_cells(mut self, cells: impl IntoIterator<Item = Coordinate>) -> Self {
+        for cell in cells {
+            if !self.cells.contains(&cell) {
+                self.cells.push(cell);
+            }
+        }
+        return self;
+    }
+
+    pub fn with_units(mut self, units: impl IntoIterator<Item = Unit>) -> Self {
+        for unit in units {
+            if !self.units.contains(&unit) {
+                self.units.push(unit);
+            }
+        }
+        return self;
+    }
+
+    pub fn replay(&self, sudoku: &mut Puzzle) {
+        for &(coordinate, value) in &self.placed {
+            sudoku.cell_grid[coordinate].set_value(value);
+        }
+
+        for &(coordinate, value) in &self.removed {
+            sudoku.cell_grid[coordinate].discount_value(value);
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.technique)?;
+
+        if !self.units.is_empty() {
+            write!(f, " in {}", join(&self.units, |unit| unit.to_string()))?;
+        }
+
+        if !self.cells.is_empty() {
+            write!(f, " on {}", join(&self.cells, |cell| cell.to_string()))?;
+        }
+
+        if !self.placed.is_empty() {
+            write!(f, ", placing {}", join(&self.placed, |(cell, value)| format!("{} at {}", value, cell)))?;
+        }
+
+        if !self.removed.is_empty() {
+            write!(f, ", removing {}", join(&self.removed, |(cell, value)| format!("{} from {}", value, cell)))?;
+        }
+
+        return Ok(());
+    }
+}
+
+// Every step taken to solve a puzzle, in order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SolveLog {
+    pub steps: Vector<Step>,
+}
+
+impl SolveLog {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn push(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+
+    // Starting from the puzzle the log was made from, this gets to the same place without running any techniques
+    pub fn replay(&self, sudoku: &mut Puzzle) {
+        for step in &self.steps {
+            step.replay(sudoku);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        return self.steps
+            .iterate()
+            .enumerate()
+            .map(|(index, step)| format!("{}. {}\n", index + 1, step))
+            .collect();
+    }
+}
+
+// The value and candidates of every cell at one moment, for Step::since to compare against
+pub struct Snapshot {
+    cells: Vector<(Coordinate, (Option<u8>, CandidateSet))>,
+}
+
+impl Snapshot {
+    pub fn of(cell_grid: &CellGrid) -> Self {
+        let cells = cell_grid
+            .coordinates()
+            .into_iter()
+            .map(|coordinate| {
+                let cell = &cell_grid[coordinate];
+                (coordinate, (cell.value, cell.potentially_valid_values))
+            })
+            .collect();
+
+        return Self { cells };
+    }
+}
+
+fn join<T>(items: &[T], describe: impl Fn(&T) -> String) -> String {
+    return items.iterate().map(describe).collect::<Vector<String>>().join(", ");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{format::serializer::Serializer, techniques::{hidden_singles::HiddenSingles, solver::{Solver, Technique}}};
+
+    use super::*;
+
+    #[test]
+    fn units_containing_a_cell_match_the_puzzle() {
+        let sudoku = Puzzle::default();
+        let shape = sudoku.shape();
+        let coordinate = Coordinate::new(4, 7);
+
+        for unit in Unit::containing(shape, coordinate) {
+            assert!(unit.cells(&sudoku).contains(&coordinate));
+        }
+        assert_eq!(Unit::containing(shape, coordinate)[2], Unit::Block(5));
+        assert_eq!(Unit::shared_by(shape, &[Coordinate::new(0, 0), Coordinate::new(0, 2)]), vec![Unit::Row(0), Unit::Block(0)]);
+
+        let sudoku = Puzzle::with_shape(Shape::new(2, 3).expect("test shape is valid"));
+        for coordinate in sudoku.cell_grid.coordinates() {
+            assert!(Unit::containing(sudoku.shape(), coordinate).iterate().all(|unit| unit.cells(&sudoku).contains(&coordinate)));
+        }
+    }
+
+    #[test]
+    fn observing_records_placements_and_removals() {
+        let mut sudoku = Puzzle::default();
+
+        let step = Step::observe("Test", &mut sudoku, |sudoku| {
+            sudoku.cell_grid[0][0].set_value(5);
+            sudoku.cell_grid[0][1].discount_values([1, 2]);
+            true
+        }).expect("the puzzle changed");
+
+        assert_eq!(step.placed, vec![(Coordinate::new(0, 0), 5)]);
+        assert_eq!(step.removed, vec![(Coordinate::new(0, 1), 1), (Coordinate::new(0, 1), 2)]);
+        assert!(Step::observe("Test", &mut sudoku, |_| true).is_none());
+    }
+
+    #[test]
+    fn units_with_groups_follow_the_order_of_all() {
+        let sudoku = Puzzle::default();
+
+        let units: Vector<(Unit, &CellGroup)> = Unit::with_groups(&sudoku.rows, &sudoku.columns, &sudoku.blocks).collect();
+
+        assert_eq!(units.iterate().map(|&(unit, _)| unit).collect::<Vector<_>>(), Unit::all(sudoku.shape()));
+        assert!(units.iterate().all(|&(unit, group)| unit.group(&sudoku) == group));
+    }
+
+    #[test]
+    fn one_snapshot_covers_every_change_since() {
+        let mut sudoku = Puzzle::default();
+        let before = Snapshot::of(&sudoku.cell_grid);
+
+        assert!(Step::since("Test", &before, &sudoku.cell_grid).is_none());
+
+        sudoku.cell_grid[0][0].set_value(5);
+        sudoku.cell_grid[4][4].discount_value(3);
+        let step = Step::since("Test", &before, &sudoku.cell_grid).expect("the puzzle changed");
+
+        assert_eq!(step.placed, vec![(Coordinate::new(0, 0), 5)]);
+        assert_eq!(step.removed, vec![(Coordinate::new(4, 4), 3)]);
+    }
+
+    #[test]
+    fn renders_why_and_what_changed() {
+        let step = Step::new(HiddenSingles.name())
+            .with_units([Unit::Row(2)])
+            .with_cells([Coordinate::new(2, 4)]);
+        let step = Step { placed: vec![(Coordinate::new(2, 4), 7)], ..step };
+
+        assert_eq!(step.to_string(), "Hidden singles in row 3 on r3c5, placing 7 at r3c5");
+    }
+
+    #[test]
+    fn replaying_a_log_gets_to_the_same_puzzle() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let summary = Solver::default().solve(&mut solved);
+        assert!(!summary.log.steps.is_empty());
+
+        let mut replayed = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        summary.log.replay(&mut replayed);
+
+        for coordinate in solved.cell_grid.coordinates() {
+            let expected = &solved.cell_grid[coordinate];
+            let actual = &replayed.cell_grid[coordinate];
+            assert_eq!(actual.value, expected.value);
+            assert_eq!(actual.potentially_valid_values, expected.potentially_valid_values);
+        }
+        assert_eq!(summary.log.render().lines().count(), summary.log.steps.len());
+    }
+}
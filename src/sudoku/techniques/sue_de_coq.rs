@@ -0,0 +1,223 @@
+use crate::pretty::{aliases::*, iterable::*};
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::Coordinate, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::{Step, Unit}};
+
+// Two or three cells where a block meets a line hold at least two more candidates than there are cells.
+// Add some cells from the rest of the line and some from the rest of the block, with no candidate in common
+// between those two parts, until there are exactly as many cells as candidates. Every cell sees the others in its
+// part, so each candidate is placed exactly once: the line part's values stay in the line, the block part's in the block,
+// and anything else is held at the intersection.
+#[derive(Debug)]
+pub struct SueDeCoq {
+    pub intersection: Vector<Coordinate>,
+    pub line_cells: Vector<Coordinate>,
+    pub block_cells: Vector<Coordinate>,
+    pub eliminations: Vector<(Coordinate, u8)>,
+}
+
+pub struct SueDeCoqTechnique;
+
+impl Technique for SueDeCoqTechnique {
+    fn name(&self) -> StringSlice<'static> {
+        return "Sue de Coq";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 50;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let patterns = find_sue_de_coq(sudoku);
+        let pattern_cells: Vector<_> = patterns
+            .iterate()
+            .flat_map(|pattern| pattern.intersection.iterate().chain(pattern.line_cells.iterate()).chain(pattern.block_cells.iterate()).copied())
+            .collect();
+        let pattern_units: Vector<_> = patterns.iterate().flat_map(|pattern| Unit::shared_by(sudoku.shape(), &pattern.intersection)).collect();
+
+        let step = Step::observe(self.name(), sudoku, |sudoku| eliminate_sue_de_coq(sudoku, patterns));
+        return StepResult::from(step.map(|step| step.with_units(pattern_units).with_cells(pattern_cells)));
+    }
+}
+
+fn eliminate_sue_de_coq(sudoku: &mut Puzzle, patterns: Vector<SueDeCoq>) -> bool {
+
+    let mut any_eliminated = false;
+
+    for pattern in patterns {
+        for (coordinate, value) in pattern.eliminations {
+            any_eliminated = sudoku.cell_grid[coordinate].discount_value(value) || any_eliminated;
+        }
+    }
+
+    return any_eliminated;
+}
+
+// Only patterns that would eliminate something are returned
+pub fn find_sue_de_coq(sudoku: &Puzzle) -> Vector<SueDeCoq> {
+
+    let mut patterns = Vector::new();
+
+    for block in sudoku.blocks.iterate() {
+    for line in sudoku.rows.iterate().chain(sudoku.columns.iterate()) {
+
+        let intersection = unsolved_cells(sudoku, block, |cell| line.contains(cell));
+        if intersection.len() < 2 {
+            continue;
+        }
+
+        let line_rest = unsolved_cells(sudoku, line, |cell| !block.contains(cell));
+        let block_rest = unsolved_cells(sudoku, block, |cell| !line.contains(cell));
+
+        for size in 2..=intersection.len() {
+            for core in intersection.combinations_of_size(size) {
+                patterns.extend(patterns_from_core(sudoku, &core, &intersection, &line_rest, &block_rest));
+            }
+        }
+    }}
+
+    return patterns;
+}
+
+fn patterns_from_core(sudoku: &Puzzle, core: &[Coordinate], intersection: &[Coordinate], line_rest: &[Coordinate], block_rest: &[Coordinate]) -> Vector<SueDeCoq> {
+
+    let mut patterns = Vector::new();
+
+    let core_candidates = union_of(sudoku, core);
+    let extra_cells_needed = core_candidates.len() - core.len();
+    if extra_cells_needed < 2 {
+        return patterns;
+    }
+
+    // Cells bringing in candidates from outside the intersection would need more cells again, so only those within it are used
+    let line_options: Vector<Coordinate> = line_rest.iterate().copied().filter(|&cell| candidates_at(sudoku, cell).is_subset_of(core_candidates)).collect();
+    let block_options: Vector<Coordinate> = block_rest.iterate().copied().filter(|&cell| candidates_at(sudoku, cell).is_subset_of(core_candidates)).collect();
+
+    // Intersection cells left out of the pattern are in both the line and the block
+    let others_in_intersection: Vector<Coordinate> = intersection.iterate().copied().filter(|cell| !core.contains(cell)).collect();
+    let line_targets: Vector<Coordinate> = line_rest.iterate().chain(others_in_intersection.iterate()).copied().collect();
+    let block_targets: Vector<Coordinate> = block_rest.iterate().chain(others_in_intersection.iterate()).copied().collect();
+
+    for line_size in 1..extra_cells_needed {
+        for line_cells in line_options.combinations_of_size(line_size) {
+            for block_cells in block_options.combinations_of_size(extra_cells_needed - line_size) {
+
+                let line_candidates = union_of(sudoku, &line_cells);
+                let block_candidates = union_of(sudoku, &block_cells);
+
+                if !(line_candidates & block_candidates).is_empty() {
+                    continue;
+                }
+
+                let mut eliminations = Vector::new();
+                eliminate_from(sudoku, &line_targets, &line_cells, core_candidates - block_candidates, &mut eliminations);
+                eliminate_from(sudoku, &block_targets, &block_cells, core_candidates - line_candidates, &mut eliminations);
+
+                if !eliminations.is_empty() {
+                    patterns.push(SueDeCoq {
+                        intersection: core.to_vec(),
+                        line_cells: line_cells.clone(),
+                        block_cells,
+                        eliminations
+                    });
+                }
+            }
+        }
+    }
+
+    return patterns;
+}
+
+fn eliminate_from(sudoku: &Puzzle, targets: &[Coordinate], pattern_cells: &[Coordinate], removed: CandidateSet, eliminations: &mut Vector<(Coordinate, u8)>) {
+    for &cell in targets {
+        if pattern_cells.contains(&cell) {
+            continue;
+        }
+
+        for value in candidates_at(sudoku, cell) & removed {
+            if !eliminations.contains(&(cell, value)) {
+                eliminations.push((cell, value));
+            }
+        }
+    }
+}
+
+fn unsolved_cells(sudoku: &Puzzle, group: &CellGroup, include: impl Fn(Coordinate) -> bool) -> Vector<Coordinate> {
+    return group
+        .unsolved(&sudoku.cell_grid)
+        .into_iter()
+        .filter(|&cell| include(cell))
+        .collect();
+}
+
+fn union_of(sudoku: &Puzzle, cells: &[Coordinate]) -> CandidateSet {
+    return cells.iterate().fold(CandidateSet::new(), |candidates, &cell| candidates | candidates_at(sudoku, cell));
+}
+
+fn candidates_at(sudoku: &Puzzle, coordinate: Coordinate) -> CandidateSet {
+    return sudoku.cell_grid[coordinate].potentially_valid_values;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::consts::PUZZLE_MAXIMUM_VALUE, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{backtracking_brute_force::find_solutions, implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    fn keep_only_candidates(sudoku: &mut Puzzle, coordinate: Coordinate, values: &[u8]) {
+        let discounted: Vector<u8> = (1..=PUZZLE_MAXIMUM_VALUE).filter(|value| !values.contains(value)).collect();
+        sudoku.cell_grid[coordinate].discount_values(discounted);
+    }
+
+    #[test]
+    fn splits_intersection_values_between_line_and_block() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2, 3, 4]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 1), &[1, 2, 3, 4]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 5), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 1), &[3, 4]);
+
+        let patterns = find_sue_de_coq(&sudoku);
+        let pattern = patterns
+            .iterate()
+            .find(|pattern| pattern.line_cells == vec![Coordinate::new(0, 5)] && pattern.block_cells == vec![Coordinate::new(1, 1)])
+            .expect("the two bi-value cells complete the pattern");
+        assert_eq!(pattern.intersection, vec![Coordinate::new(0, 0), Coordinate::new(0, 1)]);
+
+        assert_ne!(SueDeCoqTechnique.apply(&mut sudoku), StepResult::NoProgress);
+        assert_eq!(candidates_at(&sudoku, Coordinate::new(0, 8)), CandidateSet::from([3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(candidates_at(&sudoku, Coordinate::new(2, 2)), CandidateSet::from([1, 2, 5, 6, 7, 8, 9]));
+        assert_eq!(candidates_at(&sudoku, Coordinate::new(0, 2)), CandidateSet::from([5, 6, 7, 8, 9]));
+        assert_eq!(candidates_at(&sudoku, Coordinate::new(4, 5)).len(), 9);
+    }
+
+    #[test]
+    fn no_pattern_when_line_and_block_cells_share_a_candidate() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2, 3, 4]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 1), &[1, 2, 3, 4]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 5), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 1), &[2, 3]);
+
+        assert!(!find_sue_de_coq(&sudoku).iterate().any(|pattern| pattern.line_cells == vec![Coordinate::new(0, 5)] && pattern.block_cells == vec![Coordinate::new(1, 1)]));
+    }
+
+    #[test]
+    fn never_contradicts_the_solution() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        let solution = find_solutions(&mut solved, 1).remove(0);
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(SueDeCoqTechnique)]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        for coordinate in sudoku.cell_grid.coordinates() {
+            let cell = &sudoku.cell_grid[coordinate];
+            let expected = solution[coordinate.row][coordinate.column].expect("solution is complete");
+            assert!(cell.value == Some(expected) || cell.potentially_valid_values.contains(expected));
+        }
+    }
+}
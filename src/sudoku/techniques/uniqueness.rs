@@ -0,0 +1,523 @@
+use crate::pretty::{aliases::*, iterable::*};
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::Coordinate, puzzle::Puzzle, validatable_units::CellGroup};
+
+use super::{solver::{StepResult, Technique}, step::Step};
+
+// These techniques assume there is only one solution, and will happily make wrong deductions when there isn't.
+// Callers have to say which they have, rather than us paying for a full search to find out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Uniqueness {
+    Known,
+    Unverified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniquenessPatternKind {
+    UniqueRectangleType1,
+    UniqueRectangleType2,
+    UniqueRectangleType3,
+    UniqueRectangleType4,
+    HiddenUniqueRectangle,
+    BivalueUniversalGravePlusOne,
+}
+
+#[derive(Debug)]
+pub struct UniquenessPattern {
+    pub kind: UniquenessPatternKind,
+    pub cells: Vector<Coordinate>,
+    pub eliminations: Vector<(Coordinate, u8)>,
+    pub placements: Vector<(Coordinate, u8)>,
+}
+
+// Corners are laid out top left, top right, bottom left, bottom right, so the opposite corner of i is 3 - i,
+// the corner sharing its row is i ^ 1 and the corner sharing its column is i ^ 2
+type Rectangle = [Coordinate; 4];
+
+pub struct UniquenessPatterns {
+    pub uniqueness: Uniqueness,
+}
+
+impl Technique for UniquenessPatterns {
+    fn name(&self) -> StringSlice<'static> {
+        return "Uniqueness patterns";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 48;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        if self.uniqueness != Uniqueness::Known {
+            return StepResult::NoProgress;
+        }
+
+        let patterns = find_uniqueness_patterns(sudoku);
+        let pattern_cells: Vector<_> = patterns.iterate().flat_map(|pattern| pattern.cells.clone()).collect();
+
+        let step = Step::observe(self.name(), sudoku, |sudoku| apply_uniqueness_patterns(sudoku, patterns));
+        return StepResult::from(step.map(|step| step.with_cells(pattern_cells)));
+    }
+}
+
+fn apply_uniqueness_patterns(sudoku: &mut Puzzle, patterns: Vector<UniquenessPattern>) -> bool {
+
+    let mut any_changed = false;
+
+    for pattern in patterns {
+        for (coordinate, value) in pattern.eliminations {
+            any_changed = sudoku.cell_grid[coordinate].discount_value(value) || any_changed;
+        }
+
+        for (coordinate, value) in pattern.placements {
+            if sudoku.cell_grid[coordinate].value.is_none() {
+                sudoku.cell_grid[coordinate].set_value(value);
+                any_changed = true;
+            }
+        }
+    }
+
+    return any_changed;
+}
+
+// Only patterns that would change something are returned. Every one of them relies on the puzzle having a unique solution.
+pub fn find_uniqueness_patterns(sudoku: &Puzzle) -> Vector<UniquenessPattern> {
+
+    let mut patterns = Vector::new();
+
+    for rectangle in rectangles(sudoku) {
+        for first in sudoku.shape().values() {
+        for second in first + 1..=sudoku.shape().maximum_value() {
+            patterns.extend(unique_rectangles(sudoku, rectangle, [first, second]));
+        }}
+    }
+
+    patterns.extend(bivalue_universal_grave_plus_one(sudoku));
+    return patterns;
+}
+
+// A deadly pattern is two values filling four cells of a rectangle over exactly two blocks: they could be swapped
+// and the puzzle would still be solved, so a unique puzzle never ends up there. Given cells can't be swapped, so
+// a rectangle touching a given is not deadly; cells we solved ourselves could, so they still count.
+fn rectangles(sudoku: &Puzzle) -> Vector<Rectangle> {
+
+    let mut rectangles = Vector::new();
+
+    let dimension = sudoku.shape().dimension();
+
+    for top in 0..dimension {
+    for bottom in top + 1..dimension {
+    for left in 0..dimension {
+    for right in left + 1..dimension {
+        let rectangle = [
+            Coordinate::new(top, left),
+            Coordinate::new(top, right),
+            Coordinate::new(bottom, left),
+            Coordinate::new(bottom, right),
+        ];
+
+        let blocks_spanned = sudoku.blocks
+            .iterate()
+            .filter(|block| rectangle.iterate().any(|&corner| block.contains(corner)))
+            .count();
+        let any_given = rectangle.iterate().any(|&corner| sudoku.cell_grid[corner].is_given);
+
+        if blocks_spanned == 2 && !any_given {
+            rectangles.push(rectangle);
+        }
+    }}}}
+
+    return rectangles;
+}
+
+fn unique_rectangles(sudoku: &Puzzle, rectangle: Rectangle, values: [u8; 2]) -> Vector<UniquenessPattern> {
+
+    let mut patterns = Vector::new();
+
+    // Each corner has to be able to end up as one of the two values
+    let mut is_floor = [false; 4];
+    for (index, &corner) in rectangle.iterate().enumerate() {
+        let cell = &sudoku.cell_grid[corner];
+        match cell.value {
+            Some(value) if values.contains(&value) => is_floor[index] = true,
+            Some(_) => return patterns,
+            None if cell.potentially_valid_values.is_superset_of(CandidateSet::from(values)) => is_floor[index] = cell.potentially_valid_values.len() == 2,
+            None => return patterns,
+        }
+    }
+
+    let roof: Vector<usize> = (0..4).filter(|&index| !is_floor[index]).collect();
+
+    match roof.len() {
+        // The last corner would complete the pattern, so it can be neither value
+        1 => {
+            let corner = rectangle[roof[0]];
+            patterns.push(rectangle_pattern(UniquenessPatternKind::UniqueRectangleType1, rectangle, values.iterate().map(|&value| (corner, value)).collect()));
+        },
+        2 => {
+            let roof_cells = [rectangle[roof[0]], rectangle[roof[1]]];
+            patterns.extend(unique_rectangle_type_2(sudoku, rectangle, roof_cells, values));
+            patterns.extend(unique_rectangle_type_3(sudoku, rectangle, roof_cells, values));
+            patterns.extend(unique_rectangle_type_4(sudoku, rectangle, roof_cells, values));
+        },
+        _ => (),
+    }
+
+    if roof.len() >= 2 {
+        patterns.extend(hidden_unique_rectangle(sudoku, rectangle, values));
+    }
+
+    return patterns.into_iter().filter(|pattern| !pattern.eliminations.is_empty()).collect();
+}
+
+// Both roof cells have the same single extra value, so one of them must hold it
+fn unique_rectangle_type_2(sudoku: &Puzzle, rectangle: Rectangle, roof: [Coordinate; 2], values: [u8; 2]) -> Option<UniquenessPattern> {
+
+    let first_extras = extras(sudoku, roof[0], values);
+    if first_extras.len() != 1 || first_extras != extras(sudoku, roof[1], values) {
+        return None;
+    }
+
+    let extra = first_extras.first().expect("there is one extra");
+    let eliminations = sudoku.cell_grid
+        .coordinates()
+        .into_iter()
+        .filter(|coordinate| !roof.contains(coordinate) && candidates_at(sudoku, *coordinate).contains(extra))
+        .filter(|&coordinate| roof.iterate().all(|&corner| sudoku.sees(coordinate, corner)))
+        .map(|coordinate| (coordinate, extra))
+        .collect();
+
+    return Some(rectangle_pattern(UniquenessPatternKind::UniqueRectangleType2, rectangle, eliminations));
+}
+
+// One of the roof cells must hold an extra value, so together they act as a single cell holding all of the extras.
+// That pseudo cell can make a naked subset with other cells in a unit both roof cells share.
+fn unique_rectangle_type_3(sudoku: &Puzzle, rectangle: Rectangle, roof: [Coordinate; 2], values: [u8; 2]) -> Vector<UniquenessPattern> {
+
+    let pseudo_cell = extras(sudoku, roof[0], values) | extras(sudoku, roof[1], values);
+
+    let mut patterns = Vector::new();
+
+    if pseudo_cell.len() < 2 {
+        return patterns;
+    }
+
+    for unit in shared_units(sudoku, roof) {
+        let others: Vector<Coordinate> = unit.cells
+            .iterate()
+            .copied()
+            .filter(|coordinate| !roof.contains(coordinate) && sudoku.cell_grid[*coordinate].value.is_none())
+            .collect();
+
+        for size in 1..others.len() {
+            for subset in others.combinations_of_size(size) {
+
+                let subset_values = subset.iterate().fold(pseudo_cell, |values, &coordinate| values | candidates_at(sudoku, coordinate));
+
+                if subset_values.len() != size + 1 {
+                    continue;
+                }
+
+                let eliminations = others
+                    .iterate()
+                    .filter(|coordinate| !subset.contains(coordinate))
+                    .flat_map(|&coordinate| (candidates_at(sudoku, coordinate) & subset_values).into_iter().map(move |value| (coordinate, value)))
+                    .collect();
+
+                let mut pattern = rectangle_pattern(UniquenessPatternKind::UniqueRectangleType3, rectangle, eliminations);
+                pattern.cells.extend(subset);
+                patterns.push(pattern);
+            }
+        }
+    }
+
+    return patterns;
+}
+
+// When one of the values can only go in the roof cells within a unit they share, one of them must hold it,
+// so neither can hold the other value without completing the pattern
+fn unique_rectangle_type_4(sudoku: &Puzzle, rectangle: Rectangle, roof: [Coordinate; 2], values: [u8; 2]) -> Vector<UniquenessPattern> {
+
+    let mut patterns = Vector::new();
+
+    for unit in shared_units(sudoku, roof) {
+        for (locked, removed) in [(values[0], values[1]), (values[1], values[0])] {
+
+            let locked_to_roof = unit.cells
+                .iterate()
+                .filter(|&&cell| sudoku.cell_grid[cell].potentially_valid_values.contains(locked))
+                .all(|cell| roof.contains(cell));
+
+            if locked_to_roof {
+                patterns.push(rectangle_pattern(UniquenessPatternKind::UniqueRectangleType4, rectangle, roof.iterate().map(|&corner| (corner, removed)).collect()));
+            }
+        }
+    }
+
+    return patterns;
+}
+
+// From a corner holding only the two values, look at the opposite corner. If one value is confined to the rectangle
+// in both that corner's row and column, the opposite corner taking the other value would force the deadly pattern.
+fn hidden_unique_rectangle(sudoku: &Puzzle, rectangle: Rectangle, values: [u8; 2]) -> Vector<UniquenessPattern> {
+
+    let mut patterns = Vector::new();
+
+    if rectangle.iterate().any(|&corner| sudoku.cell_grid[corner].value.is_some()) {
+        return patterns;
+    }
+
+    for floor in 0..4 {
+        if candidates_at(sudoku, rectangle[floor]).len() != 2 {
+            continue;
+        }
+
+        let opposite = rectangle[3 - floor];
+        let along_row = rectangle[floor ^ 2];
+        let along_column = rectangle[floor ^ 1];
+
+        for (locked, removed) in [(values[0], values[1]), (values[1], values[0])] {
+
+            let row_positions = sudoku.rows[opposite.row].positions_with_candidate(&sudoku.cell_grid, locked);
+            let column_positions = sudoku.columns[opposite.column].positions_with_candidate(&sudoku.cell_grid, locked);
+
+            if row_positions.collection_equals(&[opposite.column, along_row.column]) && column_positions.collection_equals(&[opposite.row, along_column.row]) {
+                patterns.push(rectangle_pattern(UniquenessPatternKind::HiddenUniqueRectangle, rectangle, vec![(opposite, removed)]));
+            }
+        }
+    }
+
+    return patterns;
+}
+
+// With every unsolved cell down to two values bar one, the puzzle would have two solutions if that last cell lost its
+// extra value. The extra is the one that shows up three times in each of the cell's units.
+fn bivalue_universal_grave_plus_one(sudoku: &Puzzle) -> Option<UniquenessPattern> {
+
+    let unsolved: Vector<Coordinate> = sudoku.cell_grid
+        .coordinates()
+        .into_iter()
+        .filter(|&coordinate| sudoku.cell_grid[coordinate].value.is_none())
+        .collect();
+
+    let mut beyond_two: Vector<Coordinate> = unsolved.iterate().copied().filter(|&coordinate| candidates_at(sudoku, coordinate).len() != 2).collect();
+
+    if beyond_two.len() != 1 || candidates_at(sudoku, beyond_two[0]).len() != 3 {
+        return None;
+    }
+
+    let extra_cell = beyond_two.remove(0);
+    let units = sudoku.units_containing(extra_cell);
+
+    let extra = candidates_at(sudoku, extra_cell).into_iter().find(|&value| {
+        units.iterate().all(|unit| {
+            sudoku.shape().values()
+                .filter(|&other| candidates_at(sudoku, extra_cell).contains(other))
+                .all(|other| unit.positions_with_candidate(&sudoku.cell_grid, other).len() == if other == value { 3 } else { 2 })
+        })
+    })?;
+
+    return Some(UniquenessPattern {
+        kind: UniquenessPatternKind::BivalueUniversalGravePlusOne,
+        cells: vec![extra_cell],
+        eliminations: vec![],
+        placements: vec![(extra_cell, extra)]
+    });
+}
+
+fn rectangle_pattern(kind: UniquenessPatternKind, rectangle: Rectangle, eliminations: Vector<(Coordinate, u8)>) -> UniquenessPattern {
+    return UniquenessPattern {
+        kind,
+        cells: rectangle.to_vec(),
+        eliminations,
+        placements: vec![]
+    };
+}
+
+fn shared_units(sudoku: &Puzzle, cells: [Coordinate; 2]) -> Vector<&CellGroup> {
+    return sudoku
+        .units_containing(cells[0])
+        .into_iter()
+        .filter(|unit| unit.contains(cells[1]))
+        .collect();
+}
+
+fn extras(sudoku: &Puzzle, coordinate: Coordinate, values: [u8; 2]) -> CandidateSet {
+    return candidates_at(sudoku, coordinate) - CandidateSet::from(values);
+}
+
+fn candidates_at(sudoku: &Puzzle, coordinate: Coordinate) -> CandidateSet {
+    return sudoku.cell_grid[coordinate].potentially_valid_values;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::{core::consts::{PUZZLE_DIMENTION, PUZZLE_MAXIMUM_VALUE}, draw::terminal_print::draw_all_rows, format::serializer::Serializer, techniques::{backtracking_brute_force::find_solutions, implicitly_solved::SingleCandidateCells, solver::Solver}};
+
+    use super::*;
+
+    fn keep_only_candidates(sudoku: &mut Puzzle, coordinate: Coordinate, values: &[u8]) {
+        let discounted: Vector<u8> = (1..=PUZZLE_MAXIMUM_VALUE).filter(|value| !values.contains(value)).collect();
+        sudoku.cell_grid[coordinate].discount_values(discounted);
+    }
+
+    fn patterns_of_kind(sudoku: &Puzzle, kind: UniquenessPatternKind) -> Vector<UniquenessPattern> {
+        return find_uniqueness_patterns(sudoku).into_iter().filter(|pattern| pattern.kind == kind).collect();
+    }
+
+    fn set_up_floor(sudoku: &mut Puzzle) {
+        keep_only_candidates(sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(sudoku, Coordinate::new(1, 0), &[1, 2]);
+    }
+
+    #[test]
+    fn type_1_removes_both_values_from_last_corner() {
+        let mut sudoku = Puzzle::default();
+        set_up_floor(&mut sudoku);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 3), &[1, 2]);
+
+        let patterns = patterns_of_kind(&sudoku, UniquenessPatternKind::UniqueRectangleType1);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].eliminations, vec![(Coordinate::new(1, 3), 1), (Coordinate::new(1, 3), 2)]);
+
+        assert_ne!(UniquenessPatterns { uniqueness: Uniqueness::Known }.apply(&mut sudoku), StepResult::NoProgress);
+        assert_eq!(candidates_at(&sudoku, Coordinate::new(1, 3)), CandidateSet::from([3, 4, 5, 6, 7, 8, 9]));
+    }
+
+    #[test]
+    fn nothing_changes_unless_puzzle_is_known_to_be_unique() {
+        let mut sudoku = Puzzle::default();
+        set_up_floor(&mut sudoku);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 3), &[1, 2]);
+
+        assert_eq!(UniquenessPatterns { uniqueness: Uniqueness::Unverified }.apply(&mut sudoku), StepResult::NoProgress);
+        assert_eq!(candidates_at(&sudoku, Coordinate::new(1, 3)).len(), 9);
+    }
+
+    #[test]
+    fn solved_corners_count_but_givens_do_not() {
+        let mut solved = Puzzle::default();
+        set_up_floor(&mut solved);
+        solved.cell_grid[0][3].set_value(2);
+
+        assert_eq!(patterns_of_kind(&solved, UniquenessPatternKind::UniqueRectangleType1).len(), 1);
+
+        let mut seed = vec![vec![None; PUZZLE_DIMENTION]; PUZZLE_DIMENTION];
+        seed[0][3] = Some(2);
+        let mut given = Puzzle::new(&seed).expect("test data is valid");
+        set_up_floor(&mut given);
+
+        assert!(patterns_of_kind(&given, UniquenessPatternKind::UniqueRectangleType1).is_empty());
+    }
+
+    #[test]
+    fn type_2_removes_shared_extra_from_cells_seeing_both_roof_cells() {
+        let mut sudoku = Puzzle::default();
+        set_up_floor(&mut sudoku);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 3), &[1, 2, 5]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 3), &[1, 2, 5]);
+
+        let patterns = patterns_of_kind(&sudoku, UniquenessPatternKind::UniqueRectangleType2);
+
+        assert_eq!(patterns.len(), 1);
+        assert!(patterns[0].eliminations.contains(&(Coordinate::new(8, 3), 5)));
+        assert!(patterns[0].eliminations.contains(&(Coordinate::new(2, 5), 5)));
+        assert!(!patterns[0].eliminations.contains(&(Coordinate::new(0, 8), 5)));
+    }
+
+    #[test]
+    fn type_3_uses_roof_extras_in_a_naked_subset() {
+        let mut sudoku = Puzzle::default();
+        set_up_floor(&mut sudoku);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 3), &[1, 2, 5]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(1, 3), &[1, 2, 6]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(5, 3), &[5, 6]);
+
+        let patterns = patterns_of_kind(&sudoku, UniquenessPatternKind::UniqueRectangleType3);
+
+        assert_eq!(patterns.len(), 1);
+        assert!(patterns[0].cells.contains(&Coordinate::new(5, 3)));
+        assert!(patterns[0].eliminations.contains(&(Coordinate::new(7, 3), 5)));
+        assert!(patterns[0].eliminations.contains(&(Coordinate::new(7, 3), 6)));
+        assert!(!patterns[0].eliminations.iterate().any(|(coordinate, _)| coordinate.column != 3));
+    }
+
+    #[test]
+    fn type_4_removes_other_value_when_one_is_locked_to_roof() {
+        let mut sudoku = Puzzle::default();
+        set_up_floor(&mut sudoku);
+        for row in 2..PUZZLE_DIMENTION {
+            sudoku.cell_grid[row][3].discount_value(1);
+        }
+
+        let patterns = patterns_of_kind(&sudoku, UniquenessPatternKind::UniqueRectangleType4);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].eliminations, vec![(Coordinate::new(0, 3), 2), (Coordinate::new(1, 3), 2)]);
+    }
+
+    #[test]
+    fn hidden_rectangle_removes_other_value_from_opposite_corner() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        for column in [1, 2, 4, 5, 6, 7, 8] {
+            sudoku.cell_grid[1][column].discount_value(1);
+        }
+        for row in 2..PUZZLE_DIMENTION {
+            sudoku.cell_grid[row][3].discount_value(1);
+        }
+
+        let patterns = patterns_of_kind(&sudoku, UniquenessPatternKind::HiddenUniqueRectangle);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].eliminations, vec![(Coordinate::new(1, 3), 2)]);
+    }
+
+    #[test]
+    fn bug_plus_one_places_value_appearing_three_times() {
+        let mut sudoku = Puzzle::default();
+        let unsolved = [
+            (Coordinate::new(0, 0), vec![1, 2, 3]),
+            (Coordinate::new(0, 3), vec![1, 3]),
+            (Coordinate::new(0, 6), vec![2, 3]),
+            (Coordinate::new(3, 0), vec![1, 3]),
+            (Coordinate::new(6, 0), vec![2, 3]),
+            (Coordinate::new(1, 1), vec![1, 3]),
+            (Coordinate::new(2, 2), vec![2, 3]),
+        ];
+        for coordinate in sudoku.cell_grid.coordinates() {
+            match unsolved.iterate().find(|(cell, _)| *cell == coordinate) {
+                Some((_, values)) => keep_only_candidates(&mut sudoku, coordinate, values),
+                None => sudoku.cell_grid[coordinate].set_value(9),
+            }
+        }
+
+        let patterns = patterns_of_kind(&sudoku, UniquenessPatternKind::BivalueUniversalGravePlusOne);
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].placements, vec![(Coordinate::new(0, 0), 3)]);
+
+        assert_ne!(UniquenessPatterns { uniqueness: Uniqueness::Known }.apply(&mut sudoku), StepResult::NoProgress);
+        assert_eq!(sudoku.cell_grid[0][0].value, Some(3));
+    }
+
+    #[test]
+    fn never_contradicts_the_solution_of_a_unique_puzzle() {
+        let test_case = "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+        let mut solved = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        assert!(solved.has_unique_solution());
+        let solution = find_solutions(&mut solved, 1).remove(0);
+
+        let mut sudoku = Serializer::new().new_puzzle(test_case).expect("test data is valid");
+        Solver::new(vec![Box::new(SingleCandidateCells), Box::new(UniquenessPatterns { uniqueness: Uniqueness::Known })]).solve(&mut sudoku);
+        draw_all_rows(&sudoku);
+
+        for coordinate in sudoku.cell_grid.coordinates() {
+            let cell = &sudoku.cell_grid[coordinate];
+            let expected = solution[coordinate.row][coordinate.column].expect("solution is complete");
+            assert!(cell.value == Some(expected) || cell.potentially_valid_values.contains(expected));
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use crate::pretty::{aliases::*, iterable::*};
+
+use crate::sudoku::core::{candidate_set::CandidateSet, cell_grid::Coordinate, puzzle::Puzzle};
+
+use super::{solver::{StepResult, Technique}, step::Step};
+
+#[derive(Debug, PartialEq)]
+pub enum WingKind {
+    XYWing,
+    XYZWing,
+}
+
+#[derive(Debug)]
+pub struct Wing {
+    pub kind: WingKind,
+    pub pivot: Coordinate,
+    pub pincers: [Coordinate; 2],
+    pub eliminations: Vector<(Coordinate, u8)>,
+}
+
+pub struct Wings;
+
+impl Technique for Wings {
+    fn name(&self) -> StringSlice<'static> {
+        return "Wings";
+    }
+
+    fn difficulty(&self) -> u32 {
+        return 42;
+    }
+
+    fn apply(&self, sudoku: &mut Puzzle) -> StepResult {
+        let wings = find_wings(sudoku);
+        let wing_cells: Vector<_> = wings.iterate().flat_map(|wing| [wing.pivot, wing.pincers[0], wing.pincers[1]]).collect();
+
+        let step = Step::observe(self.name(), sudoku, |sudoku| eliminate_wings(sudoku, wings));
+        return StepResult::from(step.map(|step| step.with_cells(wing_cells)));
+    }
+}
+
+fn eliminate_wings(sudoku: &mut Puzzle, wings: Vector<Wing>) -> bool {
+
+    let mut any_eliminated = false;
+
+    for wing in wings {
+        for (coordinate, value) in wing.eliminations {
+            any_eliminated = sudoku.cell_grid[coordinate].discount_value(value) || any_eliminated;
+        }
+    }
+
+    return any_eliminated;
+}
+
+// Only wings that would eliminate something are returned
+pub fn find_wings(sudoku: &Puzzle) -> Vector<Wing> {
+
+    let mut wings = Vector::new();
+
+    for pivot in sudoku.cell_grid.coordinates() {
+
+        if !(2..=3).contains(&candidates_at(sudoku, pivot).len()) {
+            continue;
+        }
+
+        let possible_pincers: Vector<Coordinate> = sudoku
+            .peers_of(pivot)
+            .into_iter()
+            .filter(|&peer| candidates_at(sudoku, peer).len() == 2)
+            .collect();
+
+        for pincers in possible_pincers.combinations_of_size(2) {
+            if let Some(wing) = wing_from(sudoku, pivot, [pincers[0], pincers[1]]) {
+                wings.push(wing);
+            }
+        }
+    }
+
+    return wings;
+}
+
+// An XY-Wing is a pivot of {x, y} seeing pincers of {x, z} and {y, z}. Whichever value the pivot takes, one of the
+// pincers must be z, so z can go from anything that sees both pincers.
+// An XYZ-Wing has a pivot of {x, y, z}, which may itself be z, so z can only go from cells that also see the pivot.
+fn wing_from(sudoku: &Puzzle, pivot: Coordinate, pincers: [Coordinate; 2]) -> Option<Wing> {
+
+    let pivot_candidates = candidates_at(sudoku, pivot);
+    let first_candidates = candidates_at(sudoku, pincers[0]);
+    let second_candidates = candidates_at(sudoku, pincers[1]);
+
+    let shared_by_pincers = first_candidates & second_candidates;
+    if shared_by_pincers.len() != 1 {
+        return None;
+    }
+    let eliminated_value = shared_by_pincers.first().expect("the pincers share one value");
+
+    let mut pincer_values = first_candidates | second_candidates;
+
+    let kind = match pivot_candidates.len() {
+        2 if !pivot_candidates.contains(eliminated_value) => {
+            pincer_values.remove(eliminated_value);
+            WingKind::XYWing
+        },
+        3 => WingKind::XYZWing,
+        _ => return None,
+    };
+
+    if pincer_values != pivot_candidates {
+        return None;
+    }
+
+    let mut must_see = pincers.to_vec();
+    if kind == WingKind::XYZWing {
+        must_see.push(pivot);
+    }
+
+    let eliminations: Vector<(Coordinate, u8)> = sudoku.cell_grid
+        .coordinates()
+        .into_iter()
+        .filter(|&coordinate| coordinate != pivot && !pincers.contains(&coordinate))
+        .filter(|&coordinate| candidates_at(sudoku, coordinate).contains(eliminated_value))
+        .filter(|&coordinate| must_see.iterate().all(|&wing_cell| sudoku.sees(coordinate, wing_cell)))
+        .map(|coordinate| (coordinate, eliminated_value))
+        .collect();
+
+    if eliminations.is_empty() {
+        return None;
+    }
+
+    return Some(Wing {
+        kind,
+        pivot,
+        pincers,
+        eliminations
+    });
+}
+
+fn candidates_at(sudoku: &Puzzle, coordinate: Coordinate) -> CandidateSet {
+    return sudoku.cell_grid[coordinate].potentially_valid_values;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sudoku::core::consts::PUZZLE_MAXIMUM_VALUE;
+
+    use super::*;
+
+    fn keep_only_candidates(sudoku: &mut Puzzle, coordinate: Coordinate, values: &[u8]) {
+        let discounted: Vector<u8> = (1..=PUZZLE_MAXIMUM_VALUE).filter(|value| !values.contains(value)).collect();
+        sudoku.cell_grid[coordinate].discount_values(discounted);
+    }
+
+    #[test]
+    fn xy_wing_reports_pivot_pincers_and_eliminations() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 5), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(4, 0), &[2, 3]);
+
+        let wings = find_wings(&sudoku);
+
+        assert_eq!(wings.len(), 1);
+        assert_eq!(wings[0].kind, WingKind::XYWing);
+        assert_eq!(wings[0].pivot, Coordinate::new(0, 0));
+        assert_eq!(wings[0].pincers, [Coordinate::new(0, 5), Coordinate::new(4, 0)]);
+        assert_eq!(wings[0].eliminations, vec![(Coordinate::new(4, 5), 3)]);
+    }
+
+    #[test]
+    fn xyz_wing_only_eliminates_from_cells_that_also_see_the_pivot() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 1), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(3, 0), &[2, 3]);
+
+        let wings = find_wings(&sudoku);
+
+        assert_eq!(wings.len(), 1);
+        assert_eq!(wings[0].kind, WingKind::XYZWing);
+        assert_eq!(wings[0].eliminations, vec![(Coordinate::new(1, 0), 3), (Coordinate::new(2, 0), 3)]);
+
+        assert_ne!(Wings.apply(&mut sudoku), StepResult::NoProgress);
+        assert!(!sudoku.cell_grid[1][0].potentially_valid_values.contains(3));
+        assert!(sudoku.cell_grid[3][1].potentially_valid_values.contains(3));
+    }
+
+    #[test]
+    fn no_wing_when_pincers_share_both_values() {
+        let mut sudoku = Puzzle::default();
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 0), &[1, 2]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(0, 5), &[1, 3]);
+        keep_only_candidates(&mut sudoku, Coordinate::new(4, 0), &[1, 3]);
+
+        assert!(find_wings(&sudoku).is_empty());
+    }
+}